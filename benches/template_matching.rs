@@ -0,0 +1,86 @@
+//! Exhaustive scale sweep vs. coarse-to-fine pyramid search on a 4K frame.
+//!
+//! Run with `cargo bench --bench template_matching`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use mavis_lib::utils::DisplayArea;
+use mavis_lib::vision::ocv::{MatchOptions, SearchStrategy, find_best_match};
+use opencv::{
+    core::{self, Mat, Rect, Scalar, Size},
+    imgproc,
+    prelude::*,
+};
+use std::hint::black_box;
+
+/// Blurred noise: random, but with structure that survives downsampling.
+fn synthetic_screen(width: i32, height: i32) -> Mat {
+    let mut noise =
+        Mat::new_rows_cols_with_default(height, width, core::CV_8UC3, Scalar::all(0.0)).unwrap();
+    core::randu(&mut noise, &Scalar::all(0.0), &Scalar::all(255.0)).unwrap();
+
+    let mut screen = Mat::default();
+    imgproc::gaussian_blur_def(&noise, &mut screen, Size::new(0, 0), 3.0).unwrap();
+    screen
+}
+
+fn crop(screen: &Mat, area: Rect) -> Mat {
+    Mat::roi(screen, area).unwrap().try_clone().unwrap()
+}
+
+/// A faster search that misses the target is not a speed-up: both strategies must
+/// find the template where it was cut out.
+fn assert_same_location(
+    screen: &Mat,
+    area: Rect,
+    exhaustive: &MatchOptions,
+    pyramid: &MatchOptions,
+) {
+    let target = crop(screen, area);
+    let expected = DisplayArea::from(area);
+    let exhaustive = find_best_match(screen, &target, exhaustive).unwrap();
+    let pyramid = find_best_match(screen, &target, pyramid).unwrap();
+    assert_eq!(
+        exhaustive.area, expected,
+        "exhaustive search missed the target"
+    );
+    assert_eq!(pyramid.area, expected, "pyramid search missed the target");
+}
+
+fn bench_find_best_match(c: &mut Criterion) {
+    let screen = synthetic_screen(3840, 2160);
+    let area = Rect::new(2210, 1340, 96, 64);
+    let target = crop(&screen, area);
+
+    let exhaustive = MatchOptions::default();
+    let pyramid = MatchOptions::default().strategy(SearchStrategy::Pyramid { levels: 2 });
+    let single_exhaustive = MatchOptions::single_scale();
+    let single_pyramid =
+        MatchOptions::single_scale().strategy(SearchStrategy::Pyramid { levels: 2 });
+
+    assert_same_location(&screen, area, &exhaustive, &pyramid);
+    assert_same_location(&screen, area, &single_exhaustive, &single_pyramid);
+    // У правого нижнего края уточнение не должно терять найденного кандидата
+    let corner = Rect::new(3840 - 96, 2160 - 64, 96, 64);
+    assert_same_location(&screen, corner, &exhaustive, &pyramid);
+
+    let mut group = c.benchmark_group("find_best_match_4k");
+    group.sample_size(10);
+    group.bench_function("exhaustive", |b| {
+        b.iter(|| find_best_match(black_box(&screen), black_box(&target), &exhaustive).unwrap())
+    });
+    group.bench_function("pyramid_2_levels", |b| {
+        b.iter(|| find_best_match(black_box(&screen), black_box(&target), &pyramid).unwrap())
+    });
+    group.bench_function("single_scale_exhaustive", |b| {
+        b.iter(|| {
+            find_best_match(black_box(&screen), black_box(&target), &single_exhaustive).unwrap()
+        })
+    });
+    group.bench_function("single_scale_pyramid_2_levels", |b| {
+        b.iter(|| find_best_match(black_box(&screen), black_box(&target), &single_pyramid).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_find_best_match);
+criterion_main!(benches);
@@ -1,52 +1,71 @@
-use crate::input::mouse::{mouse_left_click, mouse_set_position};
-use crate::input::{keyboard, mouse};
+use crate::capture::{FrameSource, crop_frame};
+use crate::display;
+use crate::error::{Error, Result};
+use crate::input::mouse::mouse_left_click;
+use crate::input::{InputBackend, keyboard, mouse};
 use crate::utils::DisplayArea;
-use crate::vision::ocv::find_target_in_image;
-use crate::vision::{ocv, tsrt};
-use crate::{utils};
-use opencv::{core, imgcodecs, prelude::*};
+use crate::vision::ocv::{MatchOptions, find_target_in_image};
+use crate::vision::anchor::{self, AnchorRegion};
+use crate::vision::features::{self, FeatureOptions};
+use crate::vision::library::TemplateLibrary;
+use crate::vision::ocr_result::OcrResult;
+use crate::vision::text::TextQuery;
+use crate::vision::tsrt::OcrEngine;
+use crate::vision::ocv;
+use opencv::prelude::*;
 use std::thread;
 use std::time::{Duration, Instant};
 
 
 
-pub fn input_text_simulated(
+/// Where the click actions search for their target and how they click it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClickOptions {
+    /// Screen area searched for the target.
+    pub active_area: DisplayArea,
+    /// Bounding box of all monitors the cursor moves in, `None` to detect it with
+    /// [`display::virtual_desktop`].
+    pub desktop: Option<DisplayArea>,
+    /// Time between mouse button press and release.
+    pub delay: Duration,
+}
+
+pub fn input_text_simulated<B: InputBackend + ?Sized, F: FrameSource + ?Sized>(
+    backend: &mut B,
+    frames: &mut F,
     rec: f32,
     target: &Mat,
-    active_area: &DisplayArea,
-    resolution: (u32, u32),
-    delay: Duration,
+    options: &MatchOptions,
+    click: &ClickOptions,
     text: &str,
-) -> Result<(), String> {
-    let screenshot: Mat = match utils::screenshot_area_to_mat(active_area) {
-        Ok(mat) => mat,
-        Err(e) => return Err(format!("input_text_simulated: {}", e)),
-    };
-    let mouse_pos = match ocv::find_target_in_image(rec, &screenshot, target) {
-        Ok(relative_area) => relative_area.from_relative(active_area).get_average_point(),
-        Err(e) => return Err(format!("input_text_simulated: {}", e)),
-    };
-
-    mouse::mouse_set_position(
-        mouse_pos.0 as u32,
-        mouse_pos.1 as u32,
-        resolution.0,
-        resolution.1,
-    );
+) -> Result<()> {
+    let screenshot: Mat = frames.grab(&click.active_area)?;
+    let mouse_pos = ocv::find_target_in_image(rec, &screenshot, target, options)?
+        .from_relative(&click.active_area)
+        .get_average_point();
+    move_to(backend, mouse_pos, click.desktop)?;
 
-    mouse::mouse_left_click(delay);
-    keyboard::type_unicode_text(text);
+    mouse::mouse_left_click(backend, click.delay)?;
+    keyboard::type_unicode_text(backend, text)?;
 
     Ok(())
 }
 
-pub fn wait_for_image(
+pub fn wait_for_image<F: FrameSource + ?Sized>(
+    frames: &mut F,
     recognition: f32,
     target: &Mat,
+    options: &MatchOptions,
     active_area: &DisplayArea,
     fps_lock: u8,
     maximum_expectation: Duration,
-) -> Result<(), String> {
+) -> Result<()> {
+
+    if fps_lock == 0 {
+        return Err(Error::InvalidArgument(
+            "fps_lock must be greater than zero".to_string(),
+        ));
+    }
 
     let min_delay_between_ticks: Duration = Duration::from_secs_f64(1. / fps_lock as f64);
     let st_func_time: Instant = Instant::now();
@@ -55,17 +74,12 @@ pub fn wait_for_image(
         let st_tick_time: Instant = Instant::now();
 
         // Сделать скриншота
-        let screenshot = match utils::screenshot_area_to_mat(active_area) {
-            Ok(img) => img,
-            Err(e) => return Err(format!("Ошибка захвата скриншота: {:?}", e)),
-        };
+        let screenshot = frames.grab(active_area)?;
 
 
         // Проверка наличия шаблона
-        match ocv::is_target_on_image(recognition,&screenshot, target) {
-            Ok(true) => return Ok(()),
-            Ok(false) => (),
-            Err(e) => return Err(format!("Ошибка поиска шаблона: {:?}", e)),
+        if ocv::is_target_on_image(recognition,&screenshot, target, options)? {
+            return Ok(());
         }
 
 
@@ -80,71 +94,474 @@ pub fn wait_for_image(
 
 
     // Если все попытки не удались, возвращаем ошибку
-    Err(format!("target not founded"))
+    Err(Error::Timeout(maximum_expectation))
+}
+
+/// What [`click_on_target`] and [`click_on_text`] do when the target is not on the screen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NotFoundPolicy {
+    /// Return the [`Error::TemplateNotFound`] or [`Error::TextNotFound`] of the search.
+    #[default]
+    Error,
+    /// Search again in new frames every `interval` until `timeout` elapses, then
+    /// return [`Error::Timeout`].
+    RetryUntil { timeout: Duration, interval: Duration },
+    /// Click at this point, in virtual desktop coordinates, instead.
+    ClickAt(i32, i32),
 }
 
-pub fn click_on_target(
+/// Clicks on the center of `target` found in `click.active_area`.
+///
+/// The cursor moves in virtual desktop coordinates, so targets on any monitor can
+/// be clicked; `click.desktop` is the bounding box of all monitors, `None` to detect it.
+///
+/// # Errors
+/// If the target is not found, depending on `not_found`:
+/// - [`NotFoundPolicy::Error`] - [`Error::TemplateNotFound`]
+/// - [`NotFoundPolicy::RetryUntil`] - [`Error::Timeout`]
+/// - [`NotFoundPolicy::ClickAt`] - no error, the fallback point is clicked
+///
+/// [`Error::InvalidArgument`] if the point to click lies outside of `click.desktop`.
+/// Capture and input errors are returned as is.
+pub fn click_on_target<B: InputBackend + ?Sized, F: FrameSource + ?Sized>(
+    backend: &mut B,
+    frames: &mut F,
     recognition: f32,
     target: &Mat,
-    active_area: &DisplayArea,
-    screen_resolution: (u32,u32),
-    delay: Duration,
-) -> Result<(), String> {
-
-    // сделать скриншот
-    let screenshot = match utils::screenshot_area_to_mat(active_area) {
-        Ok(img) => img,
-        Err(e) => return Err(format!("Ошибка захвата скриншота: {:?}", e)),
-    };
+    options: &MatchOptions,
+    click: &ClickOptions,
+    not_found: NotFoundPolicy,
+) -> Result<()> {
 
+    let point = locate(not_found, || {
+        // сделать скриншот
+        let screenshot = frames.grab(&click.active_area)?;
 
-    //найти место
-    let point = match ocv::find_target_in_image(recognition,&screenshot, target) {
-        Ok(area) => area.from_relative(active_area).get_average_point(),
-        Err(e) => {
-            eprintln!("Ошибка нахождения образца: {:?}", e);
-            (960,520)
-            //return Err("click_on_target: Not founded".to_string())
-        }
-    };
+        //найти место
+        Ok(ocv::find_target_in_image(recognition,&screenshot, target, options)?
+            .from_relative(&click.active_area)
+            .get_average_point())
+    })?;
 
     //перенести мышь
-    mouse_set_position(point.0 as u32, point.1 as u32,
-         screen_resolution.0, screen_resolution.1);
+    move_to(backend, point, click.desktop)?;
 
     //кликнуть
-    mouse_left_click(delay);
+    mouse_left_click(backend, click.delay)?;
+
+    Ok(())
+}
+
+/// Runs `search` according to `not_found`, see [`NotFoundPolicy`].
+fn locate(
+    not_found: NotFoundPolicy,
+    mut search: impl FnMut() -> Result<(i32, i32)>,
+) -> Result<(i32, i32)> {
+
+    let started = Instant::now();
+    loop {
+        match search() {
+            Err(e @ (Error::TemplateNotFound { .. } | Error::TextNotFound(_))) => match not_found {
+                NotFoundPolicy::Error => return Err(e),
+                NotFoundPolicy::ClickAt(x, y) => return Ok((x, y)),
+                NotFoundPolicy::RetryUntil { timeout, interval } => {
+                    if started.elapsed() + interval > timeout {
+                        return Err(Error::Timeout(timeout));
+                    }
+                    thread::sleep(interval);
+                }
+            },
+            result => return result,
+        }
+    }
+
+}
+
+/// Moves the cursor to `point`, in virtual desktop coordinates.
+///
+/// `desktop` is the bounding box of all monitors, `None` to detect it with
+/// [`display::desktop_or_detect`]; replays and tests pass it explicitly.
+///
+/// # Errors
+/// Returns [`Error::InvalidArgument`] if `point` lies outside of the desktop, so
+/// that no click is ever sent to wherever the cursor happens to be.
+fn move_to<B: InputBackend + ?Sized>(
+    backend: &mut B,
+    point: (i32, i32),
+    desktop: Option<DisplayArea>,
+) -> Result<()> {
+
+    let desktop = display::desktop_or_detect(desktop)?;
+    mouse::mouse_set_position_virtual(backend, point.0, point.1, &desktop)
+
+}
+
+/// Clicks on the template `name` of `library`, using its manifest settings.
+///
+/// The template's region hint, if any, is searched instead of `click.active_area`.
+/// `not_found` works as in [`click_on_target`].
+pub fn click_on<B: InputBackend + ?Sized, F: FrameSource + ?Sized>(
+    backend: &mut B,
+    frames: &mut F,
+    library: &TemplateLibrary,
+    name: &str,
+    click: &ClickOptions,
+    not_found: NotFoundPolicy,
+) -> Result<()> {
+
+    let template = library.get(name)?;
+    let click = ClickOptions {
+        active_area: template.region.unwrap_or(click.active_area),
+        ..*click
+    };
+    click_on_target(
+        backend,
+        frames,
+        template.threshold,
+        template.image()?,
+        &template.options,
+        &click,
+        not_found,
+    )
+
+}
+
+/// Waits for the template `name` of `library`, see [`wait_for_image`] and [`click_on`].
+pub fn wait_for<F: FrameSource + ?Sized>(
+    frames: &mut F,
+    library: &TemplateLibrary,
+    name: &str,
+    active_area: &DisplayArea,
+    fps_lock: u8,
+    maximum_expectation: Duration,
+) -> Result<()> {
+
+    let template = library.get(name)?;
+    wait_for_image(
+        frames,
+        template.threshold,
+        template.image()?,
+        &template.options,
+        template.region.as_ref().unwrap_or(active_area),
+        fps_lock,
+        maximum_expectation,
+    )
+
+}
+
+/// Finds the template `name` in `region` of the template `anchor_name`.
+///
+/// The anchor is searched in its region hint or `active_area`; the target region is
+/// clipped to `active_area`. Both are searched in a single capture covering the two
+/// areas. Returns the target area in screen coordinates.
+pub fn find_near<F: FrameSource + ?Sized>(
+    frames: &mut F,
+    library: &TemplateLibrary,
+    anchor_name: &str,
+    region: &AnchorRegion,
+    name: &str,
+    active_area: &DisplayArea,
+) -> Result<DisplayArea> {
+    let anchor_template = library.get(anchor_name)?;
+    let template = library.get(name)?;
+    let anchor_area = anchor_template.region.unwrap_or(*active_area);
+
+    // Якорь и цель ищутся на одном снимке
+    let grabbed = anchor_area.union(active_area);
+    let screenshot = frames.grab(&grabbed)?;
+
+    let anchor_shot = crop_frame(&screenshot, &relative_to(&anchor_area, &grabbed))?;
+    let anchor = find_target_in_image(
+        anchor_template.threshold,
+        &anchor_shot,
+        anchor_template.image()?,
+        &anchor_template.options,
+    )?
+    .from_relative(&anchor_area);
+
+    // Цель ищется только в активной области
+    let active_shot = crop_frame(&screenshot, &relative_to(active_area, &grabbed))?;
+    Ok(anchor::find_target_near(
+        template.threshold,
+        &active_shot,
+        &relative_to(&anchor, active_area),
+        region,
+        template.image()?,
+        &template.options,
+    )?
+    .from_relative(active_area))
+}
+
+/// `area` in coordinates relative to the top-left corner of `base`.
+fn relative_to(area: &DisplayArea, base: &DisplayArea) -> DisplayArea {
+    let ((x, y), (width, height)) = area.get_rectangle();
+    let ((base_x, base_y), _) = base.get_points();
+    DisplayArea::from_rectangle(x - base_x, y - base_y, width, height)
+}
+
+/// Clicks on the template `name` found in `region` of `anchor_name`, see [`find_near`].
+pub fn click_on_near<B: InputBackend + ?Sized, F: FrameSource + ?Sized>(
+    backend: &mut B,
+    frames: &mut F,
+    library: &TemplateLibrary,
+    anchor_name: &str,
+    region: &AnchorRegion,
+    name: &str,
+    click: &ClickOptions,
+) -> Result<()> {
+
+    let point = find_near(frames, library, anchor_name, region, name, &click.active_area)?
+        .get_average_point();
+
+    move_to(backend, point, click.desktop)?;
+    mouse_left_click(backend, click.delay)?;
 
     Ok(())
+
 }
 
 pub fn find_object(
     recognition: f32,
     source: &Mat,
     target: &Mat,
-) -> Result<DisplayArea,String> {
+    options: &MatchOptions,
+) -> Result<DisplayArea> {
 
-    match find_target_in_image(recognition, source, target) {
-        Ok(area)=> {
-            return Ok(area)
-        }
-        Err(e)=>{
-            return Err(format!("find_object: Error occurated from finding object: {}",e).to_string())
-        }
-    }
+    find_target_in_image(recognition, source, target, options)
 
 }
 
+/// Same as [`find_object`], but locates `target` by keypoints, so it survives
+/// rotation and perspective distortion. Returns the bounding box of the target.
+pub fn find_object_by_features(
+    source: &Mat,
+    target: &Mat,
+    options: &FeatureOptions,
+) -> Result<DisplayArea> {
+
+    Ok(features::find_target_by_features(source, target, options)?.area)
+
+}
+
+pub fn find_objects(
+    recognition: f32,
+    source: &Mat,
+    target: &Mat,
+    options: &MatchOptions,
+    max_overlap: f64,
+    order: ocv::MatchOrder,
+) -> Result<Vec<DisplayArea>> {
+
+    let matches = ocv::find_all_targets(recognition, source, target, options, max_overlap, order)?;
+    Ok(matches.into_iter().map(|m| m.area).collect())
+
+}
+
+/// Recognizes the text of `img` with `engine`, see [`OcrEngine::recognize`].
+///
+/// The image is first run through the preprocessing of the engine options, e.g.
+/// `OcrOptions::default().preprocess(Preprocessor::ui_label())`.
 pub fn extract_text(
+    engine: &mut OcrEngine,
     img: &Mat,
-    path_to_cache_file: &str,
-) -> Result<String, String> {
+) -> Result<String> {
+
+    engine.recognize(img)
+
+}
 
+/// Recognizes the words of `active_area`, see [`OcrEngine::recognize_layout`].
+///
+/// Returns the areas in screen coordinates.
+pub fn extract_text_layout<F: FrameSource + ?Sized>(
+    engine: &mut OcrEngine,
+    frames: &mut F,
+    active_area: &DisplayArea,
+) -> Result<OcrResult> {
+
+    let screenshot = frames.grab(active_area)?;
+    Ok(engine.recognize_layout(&screenshot)?.offset_by(active_area))
+
+}
+
+/// Finds `query` in the text of `active_area`, see [`TextQuery::find`].
+///
+/// Returns the area of the matched words in screen coordinates.
+///
+/// # Errors
+/// Returns [`Error::TextNotFound`] if no words match, otherwise the errors of
+/// [`OcrEngine::recognize_layout`].
+pub fn find_text<F: FrameSource + ?Sized>(
+    engine: &mut OcrEngine,
+    frames: &mut F,
+    query: &TextQuery,
+    active_area: &DisplayArea,
+) -> Result<DisplayArea> {
+
+    let layout = extract_text_layout(engine, frames, active_area)?;
+    query
+        .find(&layout)
+        .map(|found| found.area)
+        .ok_or_else(|| Error::TextNotFound(format!("{:?}", query.pattern())))
 
-    let _ = imgcodecs::imwrite(path_to_cache_file, img, &core::Vector::new());
-    let text = tsrt::read_text_from_image(path_to_cache_file);
-    let _ = std::fs::remove_file(path_to_cache_file);
+}
+
+/// Clicks on the center of `query` found in `click.active_area`, see [`find_text`].
+///
+/// `not_found` works as in [`click_on_target`], with [`Error::TextNotFound`].
+pub fn click_on_text<B: InputBackend + ?Sized, F: FrameSource + ?Sized>(
+    backend: &mut B,
+    frames: &mut F,
+    engine: &mut OcrEngine,
+    query: &TextQuery,
+    click: &ClickOptions,
+    not_found: NotFoundPolicy,
+) -> Result<()> {
+
+    let point = locate(not_found, || {
+        Ok(find_text(engine, frames, query, &click.active_area)?.get_average_point())
+    })?;
+
+    move_to(backend, point, click.desktop)?;
+    mouse_left_click(backend, click.delay)?;
 
-    Ok(text)
+    Ok(())
+
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::ImageFileSource;
+    use crate::input::{InputEvent, MouseButton, RecordingBackend};
+    use opencv::core::{self, Rect, Scalar, Size, Vector};
+    use opencv::{imgcodecs, imgproc};
+    use std::path::{Path, PathBuf};
+
+    fn desktop() -> DisplayArea {
+        DisplayArea::from_rectangle(0, 0, 800, 600)
+    }
+
+    /// Blurred noise: every patch of it is unique.
+    fn noise(width: i32, height: i32) -> Mat {
+        let mut noise =
+            Mat::new_rows_cols_with_default(height, width, core::CV_8UC3, Scalar::all(0.0))
+                .unwrap();
+        core::randu(&mut noise, &Scalar::all(0.0), &Scalar::all(255.0)).unwrap();
+        let mut blurred = Mat::default();
+        imgproc::gaussian_blur_def(&noise, &mut blurred, Size::new(0, 0), 3.0).unwrap();
+        blurred
+    }
+
+    /// Saves a noise screen where `ImageFileSource` can replay it.
+    fn recorded_screen(name: &str) -> (Mat, PathBuf) {
+        let screen = noise(800, 600);
+        let path = std::env::temp_dir().join(format!("mavis_{}_{}.png", name, std::process::id()));
+        imgcodecs::imwrite(path.to_str().unwrap(), &screen, &Vector::new()).unwrap();
+        (screen, path)
+    }
+
+    fn replay(path: &Path) -> ImageFileSource {
+        let frames = ImageFileSource::open(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        frames
+    }
+
+    fn events(backend: &RecordingBackend) -> Vec<InputEvent> {
+        backend
+            .events()
+            .iter()
+            .map(|recorded| recorded.event.clone())
+            .collect()
+    }
+
+    /// Searches a 400x300 part of the screen and clicks without a delay.
+    fn click_options() -> ClickOptions {
+        ClickOptions {
+            active_area: DisplayArea::from_rectangle(400, 100, 400, 300),
+            desktop: Some(desktop()),
+            delay: Duration::ZERO,
+        }
+    }
+
+    fn click_at(x: i32, y: i32) -> Vec<InputEvent> {
+        vec![
+            InputEvent::MoveVirtual {
+                x,
+                y,
+                desktop: desktop(),
+            },
+            InputEvent::ButtonDown(MouseButton::Left),
+            InputEvent::ButtonUp(MouseButton::Left),
+        ]
+    }
+
+    #[test]
+    fn click_on_target_clicks_the_center_in_screen_coordinates() {
+        let (screen, path) = recorded_screen("click_on_target");
+        let area = Rect::new(500, 120, 64, 48);
+        let target = Mat::roi(&screen, area).unwrap().try_clone().unwrap();
+        let mut frames = replay(&path);
+        let mut backend = RecordingBackend::new();
+
+        click_on_target(
+            &mut backend,
+            &mut frames,
+            0.95,
+            &target,
+            &MatchOptions::single_scale(),
+            &click_options(),
+            NotFoundPolicy::Error,
+        )
+        .unwrap();
+
+        let (x, y) = DisplayArea::from_rectangle(500, 120, 64, 48).get_average_point();
+        assert_eq!(events(&backend), click_at(x, y));
+    }
+
+    #[test]
+    fn click_on_target_retries_until_the_timeout() {
+        let (_, path) = recorded_screen("click_on_target_retry");
+        let mut frames = replay(&path);
+        let mut backend = RecordingBackend::new();
+        let timeout = Duration::from_millis(200);
+
+        let started = Instant::now();
+        let result = click_on_target(
+            &mut backend,
+            &mut frames,
+            0.95,
+            &noise(64, 48),
+            &MatchOptions::single_scale(),
+            &click_options(),
+            NotFoundPolicy::RetryUntil {
+                timeout,
+                interval: Duration::from_millis(50),
+            },
+        );
+
+        assert!(matches!(result, Err(Error::Timeout(t)) if t == timeout));
+        assert!(started.elapsed() >= Duration::from_millis(150));
+        assert!(events(&backend).is_empty());
+    }
+
+    #[test]
+    fn click_on_target_clicks_the_fallback_point() {
+        let (_, path) = recorded_screen("click_on_target_fallback");
+        let mut frames = replay(&path);
+        let mut backend = RecordingBackend::new();
+
+        click_on_target(
+            &mut backend,
+            &mut frames,
+            0.95,
+            &noise(64, 48),
+            &MatchOptions::single_scale(),
+            &click_options(),
+            NotFoundPolicy::ClickAt(123, 456),
+        )
+        .unwrap();
+
+        assert_eq!(events(&backend), click_at(123, 456));
+    }
 }
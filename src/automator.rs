@@ -0,0 +1,391 @@
+use opencv::core::Mat;
+use std::time::Duration;
+
+use crate::action::{self, ClickOptions, NotFoundPolicy};
+use crate::capture::FrameSource;
+use crate::display;
+use crate::error::{Error, Result};
+use crate::input::InputBackend;
+use crate::utils::DisplayArea;
+use crate::vision::anchor::AnchorRegion;
+use crate::vision::library::TemplateLibrary;
+use crate::vision::ocv::{MatchOptions, MatchOrder};
+use crate::vision::text::TextQuery;
+use crate::vision::tsrt::OcrEngine;
+
+/// Automation session: an input backend, a frame source, the screen geometry and
+/// the defaults every `action` call would otherwise repeat.
+///
+/// ```ignore
+/// let mut bot = Automator::builder(WinApiBackend::new(), ScreenSource::new())
+///     .recognition(0.9)
+///     .click_delay(Duration::from_millis(80))
+///     .library(TemplateLibrary::open("templates")?)
+///     .build()?;
+///
+/// bot.wait_for("login_form")?;
+/// bot.click_on("login_button")?;
+/// ```
+pub struct Automator<B: InputBackend, F: FrameSource> {
+    backend: B,
+    frames: F,
+    library: Option<TemplateLibrary>,
+    ocr: Option<OcrEngine>,
+    recognition: f32,
+    match_options: MatchOptions,
+    active_area: DisplayArea,
+    desktop: DisplayArea,
+    click_delay: Duration,
+    fps_lock: u8,
+    timeout: Duration,
+    not_found: NotFoundPolicy,
+}
+
+/// Builder of an [`Automator`], see [`Automator::builder`].
+pub struct AutomatorBuilder<B: InputBackend, F: FrameSource> {
+    backend: B,
+    frames: F,
+    library: Option<TemplateLibrary>,
+    ocr: Option<OcrEngine>,
+    recognition: f32,
+    match_options: MatchOptions,
+    active_area: Option<DisplayArea>,
+    desktop: Option<DisplayArea>,
+    click_delay: Duration,
+    fps_lock: u8,
+    timeout: Duration,
+    not_found: NotFoundPolicy,
+}
+
+impl<B: InputBackend, F: FrameSource> AutomatorBuilder<B, F> {
+    /// Default recognition threshold for templates passed as images.
+    pub fn recognition(mut self, recognition: f32) -> Self {
+        self.recognition = recognition;
+        self
+    }
+
+    pub fn match_options(mut self, options: MatchOptions) -> Self {
+        self.match_options = options;
+        self
+    }
+
+    /// Screen area searched by default; the whole virtual desktop if not set.
+    pub fn active_area(mut self, area: DisplayArea) -> Self {
+        self.active_area = Some(area);
+        self
+    }
+
+    /// Virtual desktop the cursor moves in (the bounding box of all monitors);
+    /// detected if not set.
+    pub fn desktop(mut self, desktop: DisplayArea) -> Self {
+        self.desktop = Some(desktop);
+        self
+    }
+
+    /// Time between mouse button press and release.
+    pub fn click_delay(mut self, delay: Duration) -> Self {
+        self.click_delay = delay;
+        self
+    }
+
+    /// Maximum number of frames per second grabbed while waiting for a target.
+    pub fn fps_lock(mut self, fps_lock: u8) -> Self {
+        self.fps_lock = fps_lock;
+        self
+    }
+
+    /// How long the `wait_*` methods wait for a target.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// What the click methods do when the target is not found, an error by default.
+    pub fn not_found(mut self, policy: NotFoundPolicy) -> Self {
+        self.not_found = policy;
+        self
+    }
+
+    /// OCR engine used by the text methods; a default [`OcrEngine`] is created on
+    /// first use if not set.
+    pub fn ocr(mut self, engine: OcrEngine) -> Self {
+        self.ocr = Some(engine);
+        self
+    }
+
+    /// Template library used by the methods taking template names.
+    pub fn library(mut self, library: TemplateLibrary) -> Self {
+        self.library = Some(library);
+        self
+    }
+
+    /// Validates the settings and detects the screen geometry that was not set.
+    ///
+    /// # Errors
+    /// - [`Error::InvalidThreshold`] for a recognition outside of `0.0..=1.0`
+    /// - [`Error::InvalidArgument`] for a zero `fps_lock`
+    /// - [`Error::DisplayQueryFailed`] if the desktop is not set and can not be detected
+    pub fn build(self) -> Result<Automator<B, F>> {
+        if !(0.0..=1.0).contains(&self.recognition) {
+            return Err(Error::InvalidThreshold(self.recognition));
+        }
+        if self.fps_lock == 0 {
+            return Err(Error::InvalidArgument(
+                "fps_lock must be greater than zero".to_string(),
+            ));
+        }
+
+        let desktop = display::desktop_or_detect(self.desktop)?;
+        let active_area = self.active_area.unwrap_or(desktop);
+
+        Ok(Automator {
+            backend: self.backend,
+            frames: self.frames,
+            library: self.library,
+            ocr: self.ocr,
+            recognition: self.recognition,
+            match_options: self.match_options,
+            active_area,
+            desktop,
+            click_delay: self.click_delay,
+            fps_lock: self.fps_lock,
+            timeout: self.timeout,
+            not_found: self.not_found,
+        })
+    }
+}
+
+impl<B: InputBackend, F: FrameSource> Automator<B, F> {
+    /// Starts the configuration of a session driving `backend` and looking at `frames`.
+    ///
+    /// Defaults: recognition 0.8, [`MatchOptions::default`], whole desktop,
+    /// detected virtual desktop, 50 ms click delay, 30 fps, 10 s timeout, no library,
+    /// [`NotFoundPolicy::Error`].
+    pub fn builder(backend: B, frames: F) -> AutomatorBuilder<B, F> {
+        AutomatorBuilder {
+            backend,
+            frames,
+            library: None,
+            ocr: None,
+            recognition: 0.8,
+            match_options: MatchOptions::default(),
+            active_area: None,
+            desktop: None,
+            click_delay: Duration::from_millis(50),
+            fps_lock: 30,
+            timeout: Duration::from_secs(10),
+            not_found: NotFoundPolicy::Error,
+        }
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    pub fn frames_mut(&mut self) -> &mut F {
+        &mut self.frames
+    }
+
+    pub fn library(&self) -> Option<&TemplateLibrary> {
+        self.library.as_ref()
+    }
+
+    pub fn active_area(&self) -> &DisplayArea {
+        &self.active_area
+    }
+
+    pub fn set_active_area(&mut self, area: DisplayArea) {
+        self.active_area = area;
+    }
+
+    pub fn desktop(&self) -> &DisplayArea {
+        &self.desktop
+    }
+
+    /// Active area, virtual desktop and click delay of the session.
+    fn click_options(&self) -> ClickOptions {
+        ClickOptions {
+            active_area: self.active_area,
+            desktop: Some(self.desktop),
+            delay: self.click_delay,
+        }
+    }
+
+    /// Returns the backend and the frame source.
+    pub fn into_parts(self) -> (B, F) {
+        (self.backend, self.frames)
+    }
+
+    /// Grabs the active area.
+    pub fn grab(&mut self) -> Result<Mat> {
+        self.frames.grab(&self.active_area)
+    }
+
+    /// Finds `target` in the active area, see [`action::find_object`].
+    ///
+    /// Returns the area in screen coordinates.
+    pub fn find_object(&mut self, target: &Mat) -> Result<DisplayArea> {
+        let screenshot = self.grab()?;
+        Ok(
+            action::find_object(self.recognition, &screenshot, target, &self.match_options)?
+                .from_relative(&self.active_area),
+        )
+    }
+
+    /// Finds every occurrence of `target` in the active area, see [`action::find_objects`].
+    pub fn find_objects(
+        &mut self,
+        target: &Mat,
+        max_overlap: f64,
+        order: MatchOrder,
+    ) -> Result<Vec<DisplayArea>> {
+        let screenshot = self.grab()?;
+        let areas = action::find_objects(
+            self.recognition,
+            &screenshot,
+            target,
+            &self.match_options,
+            max_overlap,
+            order,
+        )?;
+        Ok(areas
+            .into_iter()
+            .map(|area| area.from_relative(&self.active_area))
+            .collect())
+    }
+
+    /// See [`action::click_on_target`].
+    pub fn click_on_target(&mut self, target: &Mat) -> Result<()> {
+        let click = self.click_options();
+        action::click_on_target(
+            &mut self.backend,
+            &mut self.frames,
+            self.recognition,
+            target,
+            &self.match_options,
+            &click,
+            self.not_found,
+        )
+    }
+
+    /// See [`action::wait_for_image`].
+    pub fn wait_for_image(&mut self, target: &Mat) -> Result<()> {
+        action::wait_for_image(
+            &mut self.frames,
+            self.recognition,
+            target,
+            &self.match_options,
+            &self.active_area,
+            self.fps_lock,
+            self.timeout,
+        )
+    }
+
+    /// Clicks on `target` and types `text`, see [`action::input_text_simulated`].
+    pub fn input_text(&mut self, target: &Mat, text: &str) -> Result<()> {
+        let click = self.click_options();
+        action::input_text_simulated(
+            &mut self.backend,
+            &mut self.frames,
+            self.recognition,
+            target,
+            &self.match_options,
+            &click,
+            text,
+        )
+    }
+
+    /// Clicks on the library template `name`, see [`action::click_on`].
+    pub fn click_on(&mut self, name: &str) -> Result<()> {
+        let click = self.click_options();
+        action::click_on(
+            &mut self.backend,
+            &mut self.frames,
+            library(&self.library)?,
+            name,
+            &click,
+            self.not_found,
+        )
+    }
+
+    /// Waits for the library template `name`, see [`action::wait_for`].
+    pub fn wait_for(&mut self, name: &str) -> Result<()> {
+        action::wait_for(
+            &mut self.frames,
+            library(&self.library)?,
+            name,
+            &self.active_area,
+            self.fps_lock,
+            self.timeout,
+        )
+    }
+
+    /// See [`action::find_near`].
+    pub fn find_near(
+        &mut self,
+        anchor_name: &str,
+        region: &AnchorRegion,
+        name: &str,
+    ) -> Result<DisplayArea> {
+        action::find_near(
+            &mut self.frames,
+            library(&self.library)?,
+            anchor_name,
+            region,
+            name,
+            &self.active_area,
+        )
+    }
+
+    /// See [`action::click_on_near`].
+    pub fn click_on_near(
+        &mut self,
+        anchor_name: &str,
+        region: &AnchorRegion,
+        name: &str,
+    ) -> Result<()> {
+        let click = self.click_options();
+        action::click_on_near(
+            &mut self.backend,
+            &mut self.frames,
+            library(&self.library)?,
+            anchor_name,
+            region,
+            name,
+            &click,
+        )
+    }
+
+    /// Finds `query` in the text of the active area, see [`action::find_text`].
+    pub fn find_text(&mut self, query: &TextQuery) -> Result<DisplayArea> {
+        let engine = ocr(&mut self.ocr)?;
+        action::find_text(engine, &mut self.frames, query, &self.active_area)
+    }
+
+    /// Clicks on `query` found in the active area, see [`action::click_on_text`].
+    pub fn click_on_text(&mut self, query: &TextQuery) -> Result<()> {
+        let click = self.click_options();
+        let engine = ocr(&mut self.ocr)?;
+        action::click_on_text(
+            &mut self.backend,
+            &mut self.frames,
+            engine,
+            query,
+            &click,
+            self.not_found,
+        )
+    }
+}
+
+fn library(library: &Option<TemplateLibrary>) -> Result<&TemplateLibrary> {
+    library.as_ref().ok_or_else(|| {
+        Error::InvalidArgument("Automator was built without a template library".to_string())
+    })
+}
+
+fn ocr(ocr: &mut Option<OcrEngine>) -> Result<&mut OcrEngine> {
+    match ocr {
+        Some(engine) => Ok(engine),
+        None => Ok(ocr.insert(OcrEngine::new()?)),
+    }
+}
@@ -0,0 +1,194 @@
+use opencv::{core, imgcodecs, prelude::*};
+use std::path::{Path, PathBuf};
+
+use super::{FrameSource, crop_frame};
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "bmp", "tiff"];
+
+fn read_frame(path: &Path) -> Result<core::Mat> {
+    let path_str = path.to_str().ok_or_else(|| {
+        Error::CaptureFailed(format!(
+            "read_frame: path is not valid UTF-8: {}",
+            path.display()
+        ))
+    })?;
+    let frame = imgcodecs::imread(path_str, imgcodecs::IMREAD_COLOR)?;
+    if frame.empty() {
+        return Err(Error::CaptureFailed(format!(
+            "read_frame: can not decode image {}",
+            path.display()
+        )));
+    }
+    Ok(frame)
+}
+
+/// Replays a single recorded full-screen image.
+///
+/// Every `grab` returns the same frame cropped to the requested area.
+pub struct ImageFileSource {
+    frame: core::Mat,
+}
+
+impl ImageFileSource {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            frame: read_frame(path.as_ref())?,
+        })
+    }
+}
+
+impl FrameSource for ImageFileSource {
+    fn grab(&mut self, area: &DisplayArea) -> Result<core::Mat> {
+        crop_frame(&self.frame, area)
+    }
+}
+
+/// Replays a directory of recorded full-screen images, one per `grab`.
+///
+/// # Behavior
+/// - Frames are ordered by file name (`png`, `jpg`, `jpeg`, `bmp`, `tiff`)
+/// - Each `grab` advances to the next frame
+/// - Once exhausted, the last frame is repeated unless looping is enabled
+pub struct ImageDirSource {
+    frames: Vec<PathBuf>,
+    next: usize,
+    looping: bool,
+    current: Option<core::Mat>,
+}
+
+impl ImageDirSource {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut frames: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                    .unwrap_or(false)
+            })
+            .collect();
+        if frames.is_empty() {
+            return Err(Error::CaptureFailed(format!(
+                "ImageDirSource: no images found in {}",
+                dir.display()
+            )));
+        }
+        frames.sort();
+
+        Ok(Self {
+            frames,
+            next: 0,
+            looping: false,
+            current: None,
+        })
+    }
+
+    /// Restarts from the first frame after the last one instead of repeating it.
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Number of frames in the directory.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+impl FrameSource for ImageDirSource {
+    fn grab(&mut self, area: &DisplayArea) -> Result<core::Mat> {
+        if self.next >= self.frames.len() && self.looping {
+            self.next = 0;
+        }
+        if self.next < self.frames.len() {
+            self.current = Some(read_frame(&self.frames[self.next])?);
+            self.next += 1;
+        }
+
+        match &self.current {
+            Some(frame) => crop_frame(frame, area),
+            None => Err(Error::CaptureFailed(
+                "ImageDirSource: no frame available".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencv::core::{Scalar, Vec3b, Vector};
+
+    /// Writes a 4x4 frame of a single `value` per file, plus a file to skip.
+    fn frame_dir(name: &str, frames: &[(&str, f64)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mavis_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, value) in frames {
+            let frame =
+                core::Mat::new_rows_cols_with_default(4, 4, core::CV_8UC3, Scalar::all(*value))
+                    .unwrap();
+            imgcodecs::imwrite(dir.join(file).to_str().unwrap(), &frame, &Vector::new()).unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "not a frame").unwrap();
+        dir
+    }
+
+    fn grab_values(frames: &mut ImageDirSource, count: usize) -> Vec<u8> {
+        (0..count)
+            .map(|_| {
+                let frame = frames
+                    .grab(&DisplayArea::from_rectangle(1, 1, 2, 2))
+                    .unwrap();
+                frame.at_2d::<Vec3b>(0, 0).unwrap()[0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dir_frames_are_replayed_in_file_name_order() {
+        let dir = frame_dir(
+            "dir_order",
+            &[
+                ("frame_2.png", 20.0),
+                ("frame_0.png", 0.0),
+                ("frame_1.bmp", 10.0),
+            ],
+        );
+
+        let mut frames = ImageDirSource::open(&dir).unwrap();
+        let values = grab_values(&mut frames, 5);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(frames.len(), 3);
+        // После последнего кадра повторяется последний
+        assert_eq!(values, vec![0, 10, 20, 20, 20]);
+    }
+
+    #[test]
+    fn looping_dir_restarts_from_the_first_frame() {
+        let dir = frame_dir("dir_looping", &[("a.png", 30.0), ("b.png", 40.0)]);
+
+        let mut frames = ImageDirSource::open(&dir).unwrap().looping(true);
+        let values = grab_values(&mut frames, 5);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(values, vec![30, 40, 30, 40, 30]);
+    }
+
+    #[test]
+    fn dir_without_images_is_an_error() {
+        let dir = frame_dir("dir_empty", &[]);
+
+        let result = ImageDirSource::open(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(result, Err(Error::CaptureFailed(_))));
+    }
+}
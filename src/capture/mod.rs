@@ -0,0 +1,90 @@
+pub mod file;
+pub mod screen;
+pub mod video;
+#[cfg(all(target_os = "linux", feature = "x11"))]
+pub mod ximage;
+
+use opencv::{core, prelude::*};
+
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+pub use file::{ImageDirSource, ImageFileSource};
+pub use screen::{ScreenSource, ScreenTarget};
+pub use video::VideoFileSource;
+#[cfg(all(target_os = "linux", feature = "x11"))]
+pub use ximage::XImageSource;
+
+/// Source of frames used by the `action` functions to look at the screen.
+///
+/// Implementations return a BGR (8UC3) `Mat` covering `area`, expressed in screen
+/// coordinates. Live sources capture the area, recorded sources crop it out of a
+/// stored full-screen frame, which makes every action replayable offline.
+pub trait FrameSource {
+    /// Returns the current frame cropped to `area`.
+    ///
+    /// # Errors
+    /// Returns an error if the frame cannot be captured/decoded or `area`
+    /// lies outside of it.
+    fn grab(&mut self, area: &DisplayArea) -> Result<core::Mat>;
+}
+
+/// Crops `area` out of a full-screen `frame`, returning an owned copy.
+pub(crate) fn crop_frame(frame: &core::Mat, area: &DisplayArea) -> Result<core::Mat> {
+    let ((x, y), (width, height)) = area.get_rectangle();
+    if x < 0 || y < 0 || x + width as i32 > frame.cols() || y + height as i32 > frame.rows() {
+        return Err(Error::CaptureFailed(format!(
+            "crop_frame: area {}x{} at ({}, {}) is outside of the {}x{} frame",
+            width,
+            height,
+            x,
+            y,
+            frame.cols(),
+            frame.rows()
+        )));
+    }
+
+    let roi = core::Mat::roi(frame, core::Rect::from(*area))?;
+    Ok(roi.try_clone()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencv::core::{Scalar, Vec3b};
+
+    #[test]
+    fn crop_frame_copies_the_area() {
+        let mut frame =
+            core::Mat::new_rows_cols_with_default(60, 80, core::CV_8UC3, Scalar::all(0.0)).unwrap();
+        *frame.at_2d_mut::<Vec3b>(55, 75).unwrap() = Vec3b::from([1, 2, 3]);
+
+        let cropped = crop_frame(&frame, &DisplayArea::from_rectangle(70, 50, 10, 10)).unwrap();
+
+        assert_eq!((cropped.cols(), cropped.rows()), (10, 10));
+        assert_eq!(
+            *cropped.at_2d::<Vec3b>(5, 5).unwrap(),
+            Vec3b::from([1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn crop_frame_rejects_areas_outside_of_the_frame() {
+        let frame =
+            core::Mat::new_rows_cols_with_default(60, 80, core::CV_8UC3, Scalar::all(0.0)).unwrap();
+
+        for area in [
+            DisplayArea::from_rectangle(-1, 0, 10, 10),
+            DisplayArea::from_rectangle(0, -1, 10, 10),
+            DisplayArea::from_rectangle(71, 0, 10, 10),
+            DisplayArea::from_rectangle(0, 51, 10, 10),
+            DisplayArea::from_rectangle(0, 0, 81, 60),
+        ] {
+            assert!(
+                matches!(crop_frame(&frame, &area), Err(Error::CaptureFailed(_))),
+                "{:?}",
+                area
+            );
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use opencv::{core, imgproc, prelude::*};
+
+use super::FrameSource;
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+/// Coordinates used by the areas passed to a [`ScreenSource`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScreenTarget {
+    /// Virtual desktop coordinates; an area may span several monitors.
+    #[default]
+    VirtualDesktop,
+    /// Coordinates relative to the top-left corner of the monitor with this id
+    /// (see [`crate::display::Monitor::id`]).
+    Monitor(u32),
+}
+
+/// Live capture of the screen through the `screenshots` crate.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScreenSource {
+    target: ScreenTarget,
+}
+
+impl ScreenSource {
+    /// Captures in virtual desktop coordinates.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Captures a single monitor, areas are relative to its top-left corner.
+    pub fn monitor(id: u32) -> Self {
+        Self {
+            target: ScreenTarget::Monitor(id),
+        }
+    }
+
+    pub fn target(&self) -> ScreenTarget {
+        self.target
+    }
+}
+
+impl FrameSource for ScreenSource {
+    /// Captures a screen area and converts it to an OpenCV Mat in BGR format.
+    ///
+    /// # Behavior
+    /// - In virtual desktop mode the parts of the area covered by each monitor are
+    ///   captured separately and stitched; parts outside of every monitor are black
+    /// - Converts RGBA screenshot to OpenCV BGR format
+    /// - On scaled monitors the captured part is resized to the requested size
+    ///
+    /// # Errors
+    /// Returns [`Error::CaptureFailed`] if the area is outside of every monitor,
+    /// the monitor does not exist, the area is not inside the captured monitor or
+    /// the capture fails.
+    fn grab(&mut self, area: &DisplayArea) -> Result<core::Mat> {
+        let screens = screenshots::Screen::all()
+            .map_err(|e| Error::CaptureFailed(format!("ScreenSource: {}", e)))?;
+
+        match self.target {
+            ScreenTarget::Monitor(id) => {
+                let screen = screens
+                    .iter()
+                    .find(|screen| screen.display_info.id == id)
+                    .ok_or_else(|| {
+                        Error::CaptureFailed(format!("ScreenSource: no monitor with id {}", id))
+                    })?;
+                capture_part(screen, area)
+            }
+            ScreenTarget::VirtualDesktop => capture_desktop(&screens, area),
+        }
+    }
+}
+
+fn screen_bounds(screen: &screenshots::Screen) -> DisplayArea {
+    let info = screen.display_info;
+    DisplayArea::from_rectangle(info.x, info.y, info.width, info.height)
+}
+
+fn capture_desktop(screens: &[screenshots::Screen], area: &DisplayArea) -> Result<core::Mat> {
+    // Область целиком на одном мониторе - без склейки
+    if let Some(screen) = screens
+        .iter()
+        .find(|screen| screen_bounds(screen).contains(area))
+    {
+        let ((x, y), _) = screen_bounds(screen).get_rectangle();
+        return capture_part(screen, &relative_to(area, x, y));
+    }
+
+    let ((area_x, area_y), (width, height)) = area.get_rectangle();
+    let mut canvas = core::Mat::new_rows_cols_with_default(
+        height as i32,
+        width as i32,
+        core::CV_8UC3,
+        core::Scalar::all(0.0),
+    )?;
+
+    let mut captured_any = false;
+    for screen in screens {
+        let bounds = screen_bounds(screen);
+        let Some(part) = area.intersection(&bounds) else {
+            continue;
+        };
+
+        let ((screen_x, screen_y), _) = bounds.get_rectangle();
+        let piece = capture_part(screen, &relative_to(&part, screen_x, screen_y))?;
+
+        let ((part_x, part_y), (part_width, part_height)) = part.get_rectangle();
+        let mut target = core::Mat::roi_mut(
+            &mut canvas,
+            core::Rect::new(
+                part_x - area_x,
+                part_y - area_y,
+                part_width as i32,
+                part_height as i32,
+            ),
+        )?;
+        piece.copy_to(&mut target)?;
+        captured_any = true;
+    }
+
+    if !captured_any {
+        return Err(Error::CaptureFailed(format!(
+            "ScreenSource: area {:?} is outside of every monitor",
+            area
+        )));
+    }
+    Ok(canvas)
+}
+
+/// `area` moved into the coordinates of a monitor whose top-left corner is (`x`, `y`).
+fn relative_to(area: &DisplayArea, x: i32, y: i32) -> DisplayArea {
+    let ((s_x, s_y), (e_x, e_y)) = area.get_points();
+    DisplayArea::from_points(s_x - x, s_y - y, e_x - x, e_y - y)
+}
+
+/// Captures `area`, relative to the monitor, and returns it as BGR of the area size.
+///
+/// `screenshots` silently clamps areas to the monitor, so an area sticking out of
+/// it is rejected here instead of being captured partially.
+fn capture_part(screen: &screenshots::Screen, area: &DisplayArea) -> Result<core::Mat> {
+    let info = screen.display_info;
+    let monitor = DisplayArea::from_rectangle(0, 0, info.width, info.height);
+    if !monitor.contains(area) {
+        return Err(Error::CaptureFailed(format!(
+            "ScreenSource: area {:?} is outside of monitor {} ({}x{})",
+            area, info.id, info.width, info.height
+        )));
+    }
+
+    let ((x, y), (width, height)) = area.get_rectangle();
+    let image = screen
+        .capture_area(x, y, width, height)
+        .map_err(|e| Error::CaptureFailed(format!("ScreenSource: {}", e)))?;
+    let (image_width, image_height) = (image.width() as i32, image.height() as i32);
+
+    let mat = unsafe {
+        let mut mat = core::Mat::new_rows_cols(image_height, image_width, core::CV_8UC4)?;
+        core::Mat::from_slice(image.as_raw())?
+            .reshape(4, image_height)?
+            .copy_to(&mut mat)?;
+        mat
+    };
+
+    let mut bgr_mat = core::Mat::default();
+    imgproc::cvt_color(
+        &mat,
+        &mut bgr_mat,
+        imgproc::COLOR_RGBA2BGR,
+        0,
+        core::AlgorithmHint::ALGO_HINT_DEFAULT,
+    )?;
+
+    if image_width == width as i32 && image_height == height as i32 {
+        return Ok(bgr_mat);
+    }
+
+    // На мониторах с масштабированием снимок может быть в физических пикселях;
+    // любое другое расхождение размеров - ошибка, а не повод растягивать кадр
+    let physical = |size: u32| (size as f32 * info.scale_factor).round() as i32;
+    let scaled =
+        (image_width - physical(width)).abs() <= 1 && (image_height - physical(height)).abs() <= 1;
+    if !scaled {
+        return Err(Error::CaptureFailed(format!(
+            "ScreenSource: captured {}x{} for a {}x{} area at scale {}",
+            image_width, image_height, width, height, info.scale_factor
+        )));
+    }
+
+    let mut resized = core::Mat::default();
+    imgproc::resize(
+        &bgr_mat,
+        &mut resized,
+        core::Size::new(width as i32, height as i32),
+        0.0,
+        0.0,
+        imgproc::INTER_AREA,
+    )?;
+    Ok(resized)
+}
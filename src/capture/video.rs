@@ -0,0 +1,76 @@
+use opencv::{core, prelude::*, videoio};
+use std::path::Path;
+
+use super::{FrameSource, crop_frame};
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+/// Replays a recorded screen video, one decoded frame per `grab`.
+///
+/// # Behavior
+/// - Each `grab` decodes the next frame
+/// - Once the video ends, the last frame is repeated unless looping is enabled
+pub struct VideoFileSource {
+    capture: videoio::VideoCapture,
+    looping: bool,
+    current: Option<core::Mat>,
+}
+
+impl VideoFileSource {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let path_str = path.to_str().ok_or_else(|| {
+            Error::CaptureFailed(format!(
+                "VideoFileSource: path is not valid UTF-8: {}",
+                path.display()
+            ))
+        })?;
+        let capture = videoio::VideoCapture::from_file(path_str, videoio::CAP_ANY)?;
+        if !capture.is_opened()? {
+            return Err(Error::CaptureFailed(format!(
+                "VideoFileSource: can not open video {}",
+                path.display()
+            )));
+        }
+
+        Ok(Self {
+            capture,
+            looping: false,
+            current: None,
+        })
+    }
+
+    /// Rewinds to the first frame when the video ends instead of repeating the last one.
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    fn read_next(&mut self) -> Result<Option<core::Mat>> {
+        let mut frame = core::Mat::default();
+        if self.capture.read(&mut frame)? && !frame.empty() {
+            return Ok(Some(frame));
+        }
+        Ok(None)
+    }
+}
+
+impl FrameSource for VideoFileSource {
+    fn grab(&mut self, area: &DisplayArea) -> Result<core::Mat> {
+        let mut frame = self.read_next()?;
+        if frame.is_none() && self.looping {
+            self.capture.set(videoio::CAP_PROP_POS_FRAMES, 0.0)?;
+            frame = self.read_next()?;
+        }
+        if frame.is_some() {
+            self.current = frame;
+        }
+
+        match &self.current {
+            Some(frame) => crop_frame(frame, area),
+            None => Err(Error::CaptureFailed(
+                "VideoFileSource: video has no frames".to_string(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use opencv::{core, imgproc, prelude::*};
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use x11::xlib;
+
+use super::FrameSource;
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+use crate::xerror::trap_errors;
+
+/// Live capture of the X11 root window through `XGetImage`.
+///
+/// Coordinates are root window pixels, so on multi-head setups the area may span
+/// several monitors. Works under Xvfb.
+pub struct XImageSource {
+    display: *mut xlib::Display,
+    root: xlib::Window,
+}
+
+impl XImageSource {
+    /// Connects to the display named by the `DISPLAY` environment variable.
+    pub fn open() -> Result<Self> {
+        Self::connect(ptr::null())
+    }
+
+    /// Connects to the display `name`, e.g. `":99"` for an Xvfb server.
+    pub fn open_display(name: &str) -> Result<Self> {
+        let name = CString::new(name)
+            .map_err(|e| Error::InvalidArgument(format!("display name: {}", e)))?;
+        Self::connect(name.as_ptr())
+    }
+
+    fn connect(name: *const c_char) -> Result<Self> {
+        let display = unsafe { xlib::XOpenDisplay(name) };
+        if display.is_null() {
+            return Err(Error::CaptureFailed(
+                "XImageSource: can not open X display".to_string(),
+            ));
+        }
+        let root = unsafe { xlib::XDefaultRootWindow(display) };
+        Ok(Self { display, root })
+    }
+}
+
+impl Drop for XImageSource {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XCloseDisplay(self.display);
+        }
+    }
+}
+
+impl FrameSource for XImageSource {
+    /// Captures a root window area and converts it to an OpenCV Mat in BGR format.
+    ///
+    /// # Behavior
+    /// - 32 bpp LSBFirst images with the usual `0xRRGGBB` masks are copied row by row
+    /// - Other TrueColor layouts, including MSBFirst servers, are decoded pixel by
+    ///   pixel with `XGetPixel`
+    ///
+    /// # Errors
+    /// Returns [`Error::CaptureFailed`] if the area is empty or not inside the root
+    /// window; the X error is reported instead of terminating the process.
+    fn grab(&mut self, area: &DisplayArea) -> Result<core::Mat> {
+        let ((x, y), (width, height)) = area.get_rectangle();
+        if width == 0 || height == 0 {
+            return Err(Error::CaptureFailed(
+                "XImageSource: capture area is empty".to_string(),
+            ));
+        }
+
+        // Область за пределами корневого окна даёт BadMatch
+        let image = unsafe {
+            trap_errors(self.display, || {
+                xlib::XGetImage(
+                    self.display,
+                    self.root,
+                    x,
+                    y,
+                    width,
+                    height,
+                    xlib::XAllPlanes(),
+                    xlib::ZPixmap,
+                )
+            })
+        };
+        let image = match image {
+            Ok(image) if !image.is_null() => image,
+            result => {
+                return Err(Error::CaptureFailed(format!(
+                    "XImageSource: XGetImage failed for {}x{} at ({}, {}): {}",
+                    width,
+                    height,
+                    x,
+                    y,
+                    result
+                        .err()
+                        .unwrap_or_else(|| "no image returned".to_string())
+                )));
+            }
+        };
+
+        let result = unsafe { ximage_to_mat(image) };
+        unsafe {
+            xlib::XDestroyImage(image);
+        }
+        result
+    }
+}
+
+/// Converts a ZPixmap `XImage` into a BGR Mat.
+///
+/// # Safety
+/// `image` must be a valid image returned by `XGetImage`.
+unsafe fn ximage_to_mat(image: *mut xlib::XImage) -> Result<core::Mat> {
+    let img = unsafe { &*image };
+    let (width, height) = (img.width, img.height);
+
+    // Байты пикселя в памяти идут как B, G, R, X только при LSBFirst
+    let standard_layout = img.bits_per_pixel == 32
+        && img.byte_order == xlib::LSBFirst
+        && img.red_mask == 0x00ff_0000
+        && img.green_mask == 0x0000_ff00
+        && img.blue_mask == 0x0000_00ff;
+
+    if standard_layout {
+        let mut bgra = core::Mat::new_rows_cols_with_default(
+            height,
+            width,
+            core::CV_8UC4,
+            core::Scalar::all(0.0),
+        )?;
+        let row_len = width as usize * 4;
+        let dst = bgra.data_bytes_mut()?;
+        for row in 0..height as usize {
+            let src = unsafe {
+                std::slice::from_raw_parts(
+                    (img.data as *const u8).add(row * img.bytes_per_line as usize),
+                    row_len,
+                )
+            };
+            dst[row * row_len..(row + 1) * row_len].copy_from_slice(src);
+        }
+
+        let mut bgr = core::Mat::default();
+        imgproc::cvt_color(
+            &bgra,
+            &mut bgr,
+            imgproc::COLOR_BGRA2BGR,
+            0,
+            core::AlgorithmHint::ALGO_HINT_DEFAULT,
+        )?;
+        return Ok(bgr);
+    }
+
+    if img.red_mask == 0 || img.green_mask == 0 || img.blue_mask == 0 {
+        return Err(Error::CaptureFailed(format!(
+            "XImageSource: unsupported visual ({} bpp, depth {})",
+            img.bits_per_pixel, img.depth
+        )));
+    }
+
+    let mut bgr = core::Mat::new_rows_cols_with_default(
+        height,
+        width,
+        core::CV_8UC3,
+        core::Scalar::all(0.0),
+    )?;
+    let dst = bgr.data_bytes_mut()?;
+    for row in 0..height {
+        for col in 0..width {
+            let pixel = unsafe { xlib::XGetPixel(image, col as c_int, row as c_int) };
+            let offset = (row as usize * width as usize + col as usize) * 3;
+            dst[offset] = scale_channel(pixel, img.blue_mask);
+            dst[offset + 1] = scale_channel(pixel, img.green_mask);
+            dst[offset + 2] = scale_channel(pixel, img.red_mask);
+        }
+    }
+    Ok(bgr)
+}
+
+/// Extracts the channel selected by `mask` from `pixel` and scales it to 0..=255.
+fn scale_channel(pixel: std::os::raw::c_ulong, mask: std::os::raw::c_ulong) -> u8 {
+    let shift = mask.trailing_zeros();
+    let max = mask >> shift;
+    (((pixel & mask) >> shift) * 255 / max) as u8
+}
@@ -0,0 +1,191 @@
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+/// A physical monitor, positioned in virtual desktop coordinates.
+///
+/// The virtual desktop is the coordinate space spanning all monitors; the primary
+/// monitor usually starts at (0, 0) and monitors placed left of or above it have
+/// negative coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Monitor {
+    pub id: u32,
+    /// Monitor area in virtual desktop coordinates.
+    pub bounds: DisplayArea,
+    /// DPI scaling of the monitor, 1.0 for 100%.
+    pub scale_factor: f32,
+    pub is_primary: bool,
+}
+
+/// DPI of a monitor at 100% scaling.
+pub const BASE_DPI: f32 = 96.0;
+
+impl Monitor {
+    /// Effective DPI of the monitor.
+    pub fn dpi(&self) -> f32 {
+        BASE_DPI * self.scale_factor
+    }
+}
+
+/// Lists the monitors reported by the platform (WinAPI, XRandR, CoreGraphics).
+///
+/// # Errors
+/// Returns [`Error::DisplayQueryFailed`] if the monitors can not be enumerated.
+pub fn monitors() -> Result<Vec<Monitor>> {
+    let screens =
+        screenshots::Screen::all().map_err(|e| Error::DisplayQueryFailed(e.to_string()))?;
+    Ok(screens
+        .iter()
+        .map(|screen| {
+            let info = screen.display_info;
+            Monitor {
+                id: info.id,
+                bounds: DisplayArea::from_rectangle(info.x, info.y, info.width, info.height),
+                scale_factor: info.scale_factor,
+                is_primary: info.is_primary,
+            }
+        })
+        .collect())
+}
+
+/// The primary monitor, or the first one if none is marked primary.
+pub fn primary_monitor() -> Result<Monitor> {
+    let monitors = monitors()?;
+    monitors
+        .iter()
+        .find(|monitor| monitor.is_primary)
+        .or_else(|| monitors.first())
+        .copied()
+        .ok_or_else(|| Error::DisplayQueryFailed("no monitors found".to_string()))
+}
+
+/// Makes the process per-monitor DPI aware.
+///
+/// Without it Windows virtualizes coordinates on scaled monitors: captures and
+/// mouse moves then disagree by the scale factor. Call once at startup, before
+/// any window is created.
+///
+/// # Errors
+/// Returns [`Error::DisplayQueryFailed`] if the awareness was already set, e.g.
+/// by the application manifest.
+#[cfg(all(windows, feature = "win32"))]
+pub fn enable_dpi_awareness() -> Result<()> {
+    use windows::Win32::UI::HiDpi::{
+        DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, SetProcessDpiAwarenessContext,
+    };
+
+    unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) }
+        .map_err(|e| Error::DisplayQueryFailed(format!("SetProcessDpiAwarenessContext: {}", e)))
+}
+
+/// Bounding box of all monitors in virtual desktop coordinates.
+///
+/// Parts of the box may not be covered by any monitor when the monitors differ in
+/// size or are not aligned.
+pub fn virtual_desktop() -> Result<DisplayArea> {
+    bounding_box(&monitors()?)
+        .ok_or_else(|| Error::DisplayQueryFailed("no monitors found".to_string()))
+}
+
+/// Returns `desktop` if given, otherwise detects it with [`virtual_desktop`].
+///
+/// Lets tests and replays pin the desktop while live runs query it.
+pub fn desktop_or_detect(desktop: Option<DisplayArea>) -> Result<DisplayArea> {
+    match desktop {
+        Some(desktop) => Ok(desktop),
+        None => virtual_desktop(),
+    }
+}
+
+/// Bounding box of `monitors`, `None` if the slice is empty.
+pub fn bounding_box(monitors: &[Monitor]) -> Option<DisplayArea> {
+    monitors
+        .iter()
+        .map(|monitor| monitor.bounds)
+        .reduce(|desktop, bounds| desktop.union(&bounds))
+}
+
+/// Lists the monitors of an X server through Xinerama.
+///
+/// Unlike [`monitors`] this talks to the given display directly, which is what
+/// a multi-head Xvfb exposes, e.g.
+/// `Xvfb :99 +xinerama -screen 0 1920x1080x24 -screen 1 1280x1024x24`.
+/// Without an active Xinerama extension the root window is reported as a single
+/// monitor. The first monitor is reported as primary.
+///
+/// # Arguments
+/// * `display_name` - Display to connect to, `None` for the `DISPLAY` environment variable
+#[cfg(all(target_os = "linux", feature = "x11"))]
+pub fn xinerama_monitors(display_name: Option<&str>) -> Result<Vec<Monitor>> {
+    use std::ffi::CString;
+    use std::os::raw::c_int;
+    use x11::{xinerama, xlib};
+
+    let name = display_name
+        .map(CString::new)
+        .transpose()
+        .map_err(|e| Error::InvalidArgument(format!("display name: {}", e)))?;
+    let display =
+        unsafe { xlib::XOpenDisplay(name.as_ref().map_or(std::ptr::null(), |name| name.as_ptr())) };
+    if display.is_null() {
+        return Err(Error::DisplayQueryFailed(
+            "can not open X display".to_string(),
+        ));
+    }
+
+    let mut monitors = Vec::new();
+    unsafe {
+        if xinerama::XineramaIsActive(display) != 0 {
+            let mut count: c_int = 0;
+            let screens = xinerama::XineramaQueryScreens(display, &mut count);
+            if !screens.is_null() {
+                for (index, screen) in std::slice::from_raw_parts(screens, count as usize)
+                    .iter()
+                    .enumerate()
+                {
+                    monitors.push(Monitor {
+                        id: screen.screen_number as u32,
+                        bounds: DisplayArea::from_rectangle(
+                            screen.x_org as i32,
+                            screen.y_org as i32,
+                            screen.width as u32,
+                            screen.height as u32,
+                        ),
+                        scale_factor: 1.0,
+                        is_primary: index == 0,
+                    });
+                }
+                xlib::XFree(screens.cast());
+            }
+        }
+
+        if monitors.is_empty() {
+            let screen = xlib::XDefaultScreen(display);
+            monitors.push(Monitor {
+                id: screen as u32,
+                bounds: DisplayArea::from_rectangle(
+                    0,
+                    0,
+                    xlib::XDisplayWidth(display, screen) as u32,
+                    xlib::XDisplayHeight(display, screen) as u32,
+                ),
+                scale_factor: 1.0,
+                is_primary: true,
+            });
+        }
+
+        xlib::XCloseDisplay(display);
+    }
+    Ok(monitors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_desktop_overrides_detection() {
+        let desktop = DisplayArea::from_rectangle(-1280, 0, 3200, 1080);
+
+        assert_eq!(desktop_or_detect(Some(desktop)).unwrap(), desktop);
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Errors returned by every fallible function of the crate.
+#[derive(Debug)]
+pub enum Error {
+    /// A frame could not be captured, decoded or cropped.
+    CaptureFailed(String),
+    /// Monitors could not be enumerated.
+    DisplayQueryFailed(String),
+    /// The input backend refused to inject an event.
+    InputFailed(String),
+    /// The template was not found; `best_score` is the best match value seen.
+    TemplateNotFound {
+        best_score: f64,
+    },
+    /// A recognition threshold outside of `0.0..=1.0`.
+    InvalidThreshold(f32),
+    /// The template (width, height) does not fit into the source (width, height).
+    TemplateLargerThanSource {
+        template: (i32, i32),
+        source: (i32, i32),
+    },
+    /// An empty image was passed; the value names the argument.
+    EmptyImage(&'static str),
+    /// The text was not found on the screen; the value is the query.
+    TextNotFound(String),
+    /// Text recognition failed.
+    OcrFailed(String),
+    /// The operation did not succeed within the given time.
+    Timeout(Duration),
+    /// An argument is out of its valid range.
+    InvalidArgument(String),
+    /// A template manifest could not be parsed or refers to invalid settings.
+    InvalidManifest(String),
+    /// No template with this name in the library.
+    UnknownTemplate(String),
+    OpenCv(opencv::Error),
+    Io(std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::CaptureFailed(reason) => write!(f, "capture failed: {}", reason),
+            Error::DisplayQueryFailed(reason) => write!(f, "display query failed: {}", reason),
+            Error::InputFailed(reason) => write!(f, "input failed: {}", reason),
+            Error::TemplateNotFound { best_score } => {
+                write!(f, "template not found (best score {:.3})", best_score)
+            }
+            Error::InvalidThreshold(value) => {
+                write!(f, "threshold must be between 0 and 1, got {}", value)
+            }
+            Error::TemplateLargerThanSource { template, source } => write!(
+                f,
+                "template {}x{} exceeds source image {}x{}",
+                template.0, template.1, source.0, source.1
+            ),
+            Error::EmptyImage(name) => write!(f, "{} image is empty", name),
+            Error::TextNotFound(query) => write!(f, "text not found: {}", query),
+            Error::OcrFailed(reason) => write!(f, "OCR failed: {}", reason),
+            Error::Timeout(duration) => write!(f, "timed out after {:?}", duration),
+            Error::InvalidArgument(reason) => write!(f, "invalid argument: {}", reason),
+            Error::InvalidManifest(reason) => write!(f, "invalid template manifest: {}", reason),
+            Error::UnknownTemplate(name) => write!(f, "unknown template \"{}\"", name),
+            Error::OpenCv(e) => write!(f, "OpenCV error: {}", e),
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::OpenCv(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<opencv::Error> for Error {
+    fn from(e: opencv::Error) -> Self {
+        Error::OpenCv(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,71 @@
+use crate::error::Result;
+use crate::utils::DisplayArea;
+
+/// Mouse buttons that can be pressed through an [`InputBackend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+/// Non-character keys that can be pressed through an [`InputBackend`].
+///
+/// Printable text is sent with [`InputBackend::type_unicode_text`] instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Enter,
+    Tab,
+    Escape,
+    Backspace,
+    Delete,
+}
+
+/// Low-level input synthesis used by the `mouse`, `keyboard` and `action` modules.
+///
+/// Implementations translate each call into platform events (WinAPI `SendInput`,
+/// XTest, uinput, ...) without adding any timing of their own: delays, trajectories
+/// and click durations are handled by the generic helpers built on top of this trait.
+///
+/// # Errors
+/// Every method returns an error if the platform refused to inject the event.
+pub trait InputBackend {
+    /// Moves the cursor to the absolute pixel position (`x`, `y`) on a screen of
+    /// `screen_width` x `screen_height` pixels.
+    fn mouse_move_absolute(
+        &mut self,
+        x: u32,
+        y: u32,
+        screen_width: u32,
+        screen_height: u32,
+    ) -> Result<()>;
+
+    /// Moves the cursor to (`x`, `y`) in virtual desktop coordinates, i.e. the
+    /// coordinate space spanning all monitors, where `desktop` is the bounding box
+    /// of all monitors (see [`crate::display::virtual_desktop`]).
+    ///
+    /// Coordinates may be negative when a monitor is placed left of or above the
+    /// primary one.
+    fn mouse_move_virtual(&mut self, x: i32, y: i32, desktop: &DisplayArea) -> Result<()>;
+
+    /// Moves the cursor by (`dx`, `dy`) pixels from its current position.
+    fn mouse_move_relative(&mut self, dx: i32, dy: i32) -> Result<()>;
+
+    /// Presses `button` at the current cursor position.
+    fn mouse_button_down(&mut self, button: MouseButton) -> Result<()>;
+
+    /// Releases `button` at the current cursor position.
+    fn mouse_button_up(&mut self, button: MouseButton) -> Result<()>;
+
+    /// Spins the vertical wheel by `delta` units (120 units per notch, positive = up).
+    fn mouse_wheel(&mut self, delta: i32) -> Result<()>;
+
+    /// Presses `key`.
+    fn key_down(&mut self, key: Key) -> Result<()>;
+
+    /// Releases `key`.
+    fn key_up(&mut self, key: Key) -> Result<()>;
+
+    /// Types `text` as a sequence of Unicode key press/release pairs.
+    fn type_unicode_text(&mut self, text: &str) -> Result<()>;
+}
@@ -1,2 +1,19 @@
+pub mod backend;
 pub mod keyboard;
 pub mod mouse;
+pub mod recorder;
+#[cfg(all(target_os = "linux", feature = "uinput"))]
+pub mod uinput;
+#[cfg(all(windows, feature = "win32"))]
+pub mod win32;
+#[cfg(all(target_os = "linux", feature = "x11"))]
+pub mod xtest;
+
+pub use backend::{InputBackend, Key, MouseButton};
+pub use recorder::{InputEvent, RecordedEvent, RecordingBackend};
+#[cfg(all(target_os = "linux", feature = "uinput"))]
+pub use uinput::UinputBackend;
+#[cfg(all(windows, feature = "win32"))]
+pub use win32::WinApiBackend;
+#[cfg(all(target_os = "linux", feature = "x11"))]
+pub use xtest::XTestBackend;
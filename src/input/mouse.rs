@@ -1,140 +1,128 @@
 use std::thread;
 use std::time::{Duration, Instant};
-use winapi::shared::minwindef::DWORD;
-use winapi::um::winuser::{
-MOUSEEVENTF_ABSOLUTE, MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP, MOUSEEVENTF_MIDDLEDOWN,
-MOUSEEVENTF_MIDDLEUP, MOUSEEVENTF_MOVE, MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP,
-MOUSEEVENTF_WHEEL, mouse_event,
-};
+
+use super::backend::{InputBackend, MouseButton};
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
 
 
 /// Simulates a left mouse button click with configurable delay between press and release.
 ///
 /// # Arguments
+/// * `backend` - Input backend that injects the events
 /// * `delay` - Duration to wait between sending the mouse-down and mouse-up events
 ///
-/// # Safety
-/// This function contains unsafe code because it calls the WinAPI `mouse_event` function directly.
-/// The caller must ensure:
-/// - The thread has rights to perform input simulation
-/// - No other thread is manipulating mouse state concurrently
+/// # Errors
+/// Returns the backend error if the events could not be injected.
 ///
 /// # Notes
-/// - Uses `MOUSEEVENTF_LEFTDOWN` and `MOUSEEVENTF_LEFTUP` flags
 /// - Sleeps the current thread during the delay
-/// - Coordinate parameters (0,0,0,0) indicate using current cursor position
-pub fn mouse_left_click(delay: Duration) {
-    unsafe {
-        mouse_event(MOUSEEVENTF_LEFTDOWN, 0, 0, 0, 0);
-        thread::sleep(delay);
-        mouse_event(MOUSEEVENTF_LEFTUP, 0, 0, 0, 0);
-    }
+/// - Clicks at the current cursor position
+pub fn mouse_left_click<B: InputBackend + ?Sized>(
+    backend: &mut B,
+    delay: Duration,
+) -> Result<()> {
+    backend.mouse_button_down(MouseButton::Left)?;
+    thread::sleep(delay);
+    backend.mouse_button_up(MouseButton::Left)
 }
 
 /// Simulates a right mouse button click with configurable delay between press and release.
 ///
 /// # Arguments
+/// * `backend` - Input backend that injects the events
 /// * `delay` - Duration to wait between sending the mouse-down and mouse-up events
 ///
-/// # Safety
-/// This function contains unsafe code because it calls the WinAPI `mouse_event` function directly.
-/// The caller must ensure:
-/// - The thread has rights to perform input simulation
-/// - No other thread is manipulating mouse state concurrently
+/// # Errors
+/// Returns the backend error if the events could not be injected.
 ///
 /// # Notes
-/// - Uses `MOUSEEVENTF_RIGHTDOWN` and `MOUSEEVENTF_RIGHTUP` flags
 /// - Sleeps the current thread during the delay
-/// - Coordinate parameters (0,0,0,0) indicate using current cursor position
+/// - Clicks at the current cursor position
 /// - Typical uses include context menu interactions
-pub fn mouse_right_click(delay: Duration) {
-    unsafe {
-        mouse_event(MOUSEEVENTF_RIGHTDOWN, 0, 0, 0, 0);
-        thread::sleep(delay);
-        mouse_event(MOUSEEVENTF_RIGHTUP, 0, 0, 0, 0);
-    }
+pub fn mouse_right_click<B: InputBackend + ?Sized>(
+    backend: &mut B,
+    delay: Duration,
+) -> Result<()> {
+    backend.mouse_button_down(MouseButton::Right)?;
+    thread::sleep(delay);
+    backend.mouse_button_up(MouseButton::Right)
 }
 
 /// Simulates a middle mouse button click with configurable delay between press and release.
 ///
 /// # Arguments
+/// * `backend` - Input backend that injects the events
 /// * `delay` - Duration between the mouse-down and mouse-up events. A zero duration may cause
 ///             some applications to not register the click properly.
 ///
-/// # Safety
-/// This function contains unsafe WinAPI calls. The caller must ensure:
-/// - The application has permission to simulate input events
-/// - No other threads are manipulating mouse state concurrently
-/// - The function is not called during system hooks that might intercept mouse events
+/// # Errors
+/// Returns the backend error if the events could not be injected.
 ///
 /// # Notes
-/// - Uses `MOUSEEVENTF_MIDDLEDOWN` and `MOUSEEVENTF_MIDDLEUP` flags
-/// - Maintains cursor position (parameters 0,0,0,0 use current position)
-pub fn mouse_middle_click(delay: Duration) {
-    unsafe {
-        mouse_event(MOUSEEVENTF_MIDDLEDOWN, 0, 0, 0, 0);
-        thread::sleep(delay);
-        mouse_event(MOUSEEVENTF_MIDDLEUP, 0, 0, 0, 0);
-    }
+/// - Maintains cursor position
+pub fn mouse_middle_click<B: InputBackend + ?Sized>(
+    backend: &mut B,
+    delay: Duration,
+) -> Result<()> {
+    backend.mouse_button_down(MouseButton::Middle)?;
+    thread::sleep(delay);
+    backend.mouse_button_up(MouseButton::Middle)
 }
 
 /// Simulates mouse wheel rotation with specified delta value.
 ///
 /// # Arguments
+/// * `backend` - Input backend that injects the events
 /// * `delta` - Wheel movement amount and direction:
 ///   - Positive: Scroll up (away from user)
 ///   - Negative: Scroll down (toward user)
 ///   - Standard increment: 120 units per "click" (WHEEL_DELTA)
 ///
-/// # Safety
-/// Contains unsafe WinAPI calls. Requirements:
-/// - Thread must have input simulation privileges
-/// - No concurrent mouse state manipulation
-/// - Delta should be multiples of 120 for expected behavior
+/// # Errors
+/// Returns the backend error if the event could not be injected.
 ///
 /// # Notes
-/// - Uses `MOUSEEVENTF_WHEEL` flag
-/// - Uses current cursor position (0,0,0,0 parameters)
-/// - For horizontal scroll, use `mouse_hwheel_spin`
-pub fn mouse_wheel_spin(delta: i32) {
-    unsafe {
-        mouse_event(MOUSEEVENTF_WHEEL, 0, 0, delta as DWORD, 0);
-    }
+/// - Delta should be multiples of 120 for expected behavior
+/// - Uses current cursor position
+pub fn mouse_wheel_spin<B: InputBackend + ?Sized>(
+    backend: &mut B,
+    delta: i32,
+) -> Result<()> {
+    backend.mouse_wheel(delta)
 }
 
 /// Simulates smooth mouse wheel scrolling by breaking movement into incremental steps.
 ///
 /// # Arguments
+/// * `backend` - Input backend that injects the events
 /// * `delta` - Total scroll amount (positive = up, negative = down)
 /// * `duration` - Total time over which to distribute the scrolling
 ///
 /// # Returns
-/// `Ok(())` on success, or `Err(&'static str)` if:
+/// `Ok(())` on success, or an error if:
 /// - `delta` is zero (would cause division by zero)
-/// - `duration` is too short for requested steps
-///
-/// # Safety
-/// Contains unsafe WinAPI calls. Requirements:
-/// - Thread must have input simulation privileges
-/// - No concurrent mouse state manipulation
+/// - the backend failed to inject an event
 ///
 /// # Notes
 /// - More realistic than single-event scrolling
 /// - Each increment sends ±1 wheel unit
 /// - Sleeps between increments for smooth effect
-/// - Uses current cursor position (0,0,0,0 parameters)
-pub fn mouse_wheel_complex(delta: i32, duration: Duration) -> Result<(), &'static str> {
+/// - Uses current cursor position
+pub fn mouse_wheel_complex<B: InputBackend + ?Sized>(
+    backend: &mut B,
+    delta: i32,
+    duration: Duration,
+) -> Result<()> {
     if delta == 0 {
-        return Err("Delta cannot be zero");
+        return Err(Error::InvalidArgument("Delta cannot be zero".to_string()));
     }
 
     let tick_time = duration.div_f32(delta.abs() as f32);
-    let tick_move: i8 = if delta > 0 { 1 } else { -1 };
+    let tick_move: i32 = if delta > 0 { 1 } else { -1 };
 
     for _ in 0..delta.abs() {
-        unsafe {
-            mouse_event(MOUSEEVENTF_WHEEL, 0, 0, tick_move as DWORD, 0);
-        }
+        backend.mouse_wheel(tick_move)?;
         thread::sleep(tick_time);
     }
     Ok(())
@@ -143,6 +131,7 @@ pub fn mouse_wheel_complex(delta: i32, duration: Duration) -> Result<(), &'stati
 /// Sets the absolute mouse position within specified screen boundaries.
 ///
 /// # Arguments
+/// * `backend` - Input backend that injects the event
 /// * `new_x` - Target X coordinate (0 = left edge, `screen_width` = right edge)
 /// * `new_y` - Target Y coordinate (0 = top edge, `screen_height` = bottom edge)
 /// * `screen_width` - Current screen width in pixels
@@ -150,50 +139,71 @@ pub fn mouse_wheel_complex(delta: i32, duration: Duration) -> Result<(), &'stati
 ///
 /// # Behavior
 /// - Silently returns if coordinates exceed screen dimensions
-/// - Converts coordinates to absolute input (0-65535 range)
-/// - Uses combined `MOUSEEVENTF_MOVE|MOUSEEVENTF_ABSOLUTE` flags
-///
-/// # Safety
-/// Contains unsafe WinAPI calls. Requirements:
-/// - Valid screen dimensions must be provided
-/// - Coordinates should be within display bounds
-pub fn mouse_set_position(new_x: u32, new_y: u32, screen_width: u32, screen_height: u32) {
+/// - Coordinate normalization is done by the backend
+///
+/// # Errors
+/// Returns the backend error if the event could not be injected.
+pub fn mouse_set_position<B: InputBackend + ?Sized>(
+    backend: &mut B,
+    new_x: u32,
+    new_y: u32,
+    screen_width: u32,
+    screen_height: u32,
+) -> Result<()> {
     if new_x > screen_width || new_y > screen_height {
-        return;
+        return Ok(());
     }
-    unsafe {
-        mouse_event(
-            MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE,
-            (new_x * 65536 / screen_width) as DWORD,
-            (new_y * 65536 / screen_height) as DWORD,
-            0,
-            0,
-        );
+    backend.mouse_move_absolute(new_x, new_y, screen_width, screen_height)
+}
+
+/// Sets the mouse position in virtual desktop coordinates, spanning all monitors.
+///
+/// # Arguments
+/// * `backend` - Input backend that injects the event
+/// * `x` - Target X coordinate, negative for monitors left of the primary one
+/// * `y` - Target Y coordinate, negative for monitors above the primary one
+/// * `desktop` - Bounding box of all monitors, see [`crate::display::virtual_desktop`]
+///
+/// # Errors
+/// - [`Error::InvalidArgument`] if the point lies outside of `desktop`
+/// - The backend error if the event could not be injected
+pub fn mouse_set_position_virtual<B: InputBackend + ?Sized>(
+    backend: &mut B,
+    x: i32,
+    y: i32,
+    desktop: &DisplayArea,
+) -> Result<()> {
+    if !desktop.contains_point((x, y)) {
+        return Err(Error::InvalidArgument(format!(
+            "point ({}, {}) is outside of the virtual desktop {:?}",
+            x, y, desktop
+        )));
     }
+    backend.mouse_move_virtual(x, y, desktop)
 }
 
 /// Moves the mouse cursor by relative pixel offsets from current position.
 ///
 /// # Arguments
+/// * `backend` - Input backend that injects the event
 /// * `x` - Horizontal movement (positive = right, negative = left)
 /// * `y` - Vertical movement (positive = down, negative = up)
 ///
 /// # Behavior
 /// - No movement occurs if both offsets are zero
-/// - Uses relative movement mode (`MOUSEEVENTF_MOVE`)
 /// - Movement is cumulative with other mouse events
 ///
-/// # Safety
-/// Contains unsafe WinAPI calls. Requirements:
-/// - Thread must have input simulation privileges
-/// - No concurrent mouse state manipulation
-pub fn mouse_movement(x: i32, y: i32) {
+/// # Errors
+/// Returns the backend error if the event could not be injected.
+pub fn mouse_movement<B: InputBackend + ?Sized>(
+    backend: &mut B,
+    x: i32,
+    y: i32,
+) -> Result<()> {
     if y == 0 && x == 0 {
-        return;
-    }
-    unsafe {
-        mouse_event(MOUSEEVENTF_MOVE, x as DWORD, y as DWORD, 0, 0);
+        return Ok(());
     }
+    backend.mouse_move_relative(x, y)
 }
 
 /// Calculates a linearly interpolated point between two positions based on time progression.
@@ -299,6 +309,7 @@ type MoveFunction = dyn Fn((f64, f64), (f64, f64), f64, f64) -> (f64, f64);
 /// Executes a controlled mouse movement between two points using a specified movement function.
 ///
 /// # Arguments
+/// * `backend` - Input backend that injects the events
 /// * `start_pos` - (x, y) starting coordinates in pixels
 /// * `end_pos` - (x, y) target coordinates in pixels
 /// * `disp_res` - (width, height) of display resolution
@@ -310,10 +321,11 @@ type MoveFunction = dyn Fn((f64, f64), (f64, f64), f64, f64) -> (f64, f64);
 ///   - Returns: current (x, y) position
 ///
 /// # Returns
-/// `Ok(())` on success, or `Err(&'static str)` if:
+/// `Ok(())` on success, or an error if:
 /// - Duration is zero
 /// - mps_lock is zero
 /// - start and end positions are identical
+/// - the backend failed to inject an event
 ///
 /// # Behavior
 /// - Maintains precise timing using mps_lock rate limiting
@@ -321,25 +333,25 @@ type MoveFunction = dyn Fn((f64, f64), (f64, f64), f64, f64) -> (f64, f64);
 /// - Uses yield-based busy waiting for precise timing
 /// - Converts positions through specified movement function
 ///
-/// # Safety
-/// - Contains unsafe mouse position calls
+/// # Notes
 /// - Movement function must produce valid screen coordinates
-pub fn mouse_set_position_complex(
+pub fn mouse_set_position_complex<B: InputBackend + ?Sized>(
+    backend: &mut B,
     start_pos: (u32, u32),
     end_pos: (u32, u32),
     disp_res: (u32, u32),
     duration: Duration,
     mps_lock: u64,
     move_fn: &MoveFunction,
-) -> Result<(), &'static str> {
+) -> Result<()> {
     if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
-        return Err("Duration must be greater than zero");
+        return Err(Error::InvalidArgument("Duration must be greater than zero".to_string()));
     }
     if mps_lock == 0 {
-        return Err("Moves per second (mps_lock) must be greater than zero");
+        return Err(Error::InvalidArgument("Moves per second (mps_lock) must be greater than zero".to_string()));
     }
     if start_pos.0 == end_pos.0 && start_pos.1 == end_pos.1 {
-        return Err("Movement(end_pos) must be greater than zero");
+        return Err(Error::InvalidArgument("Movement(end_pos) must be greater than zero".to_string()));
     }
 
     let tick_time_lock: Duration = Duration::from_secs_f64(1.0 / mps_lock as f64);
@@ -356,7 +368,7 @@ pub fn mouse_set_position_complex(
             duration.as_secs_f64(),
             fn_start_time.elapsed().as_secs_f64(),
         );
-        mouse_set_position(x_t as u32, y_t as u32, disp_res.0, disp_res.1);
+        mouse_set_position(backend, x_t as u32, y_t as u32, disp_res.0, disp_res.1)?;
 
         let elapsed_tick = tick_start.elapsed();
         if elapsed_tick < tick_time_lock {
@@ -371,13 +383,13 @@ pub fn mouse_set_position_complex(
         duration.as_secs_f64(),
         duration.as_secs_f64(),
     );
-    mouse_set_position(x_t as u32, y_t as u32, disp_res.0, disp_res.1);
-    Ok(())
+    mouse_set_position(backend, x_t as u32, y_t as u32, disp_res.0, disp_res.1)
 }
 
 /// Executes a controlled relative mouse movement using a specified movement function with sub-pixel precision.
 ///
 /// # Arguments
+/// * `backend` - Input backend that injects the events
 /// * `moving` - (x, y) relative movement in pixels
 /// * `duration` - Total movement time (must be non-zero)
 /// * `mps_lock` - Movement updates per second (must be ≥ 1)
@@ -387,10 +399,11 @@ pub fn mouse_set_position_complex(
 ///   - Returns: current (x, y) progress
 ///
 /// # Returns
-/// `Ok(())` on success, or `Err(&'static str)` if:
+/// `Ok(())` on success, or an error if:
 /// - Duration is zero
 /// - mps_lock is zero
 /// - Both movement components are zero
+/// - the backend failed to inject an event
 ///
 /// # Behavior
 /// - Maintains precise timing using mps_lock rate limiting
@@ -404,23 +417,23 @@ pub fn mouse_set_position_complex(
 /// - Fractional pixel carry-over between frames
 /// - Independent x/y axis processing
 ///
-/// # Safety
-/// - Contains unsafe mouse movement calls
+/// # Notes
 /// - Movement function should produce stable values
-pub fn mouse_movement_complex(
+pub fn mouse_movement_complex<B: InputBackend + ?Sized>(
+    backend: &mut B,
     moving: (u32, u32),
     duration: Duration,
     mps_lock: u64,
     move_fn: &MoveFunction,
-) -> Result<(), &'static str> {
+) -> Result<()> {
     if duration.as_secs() == 0 && duration.subsec_nanos() == 0 {
-        return Err("Duration must be greater than zero");
+        return Err(Error::InvalidArgument("Duration must be greater than zero".to_string()));
     }
     if mps_lock == 0 {
-        return Err("Moves per second (mps_lock) must be greater than zero");
+        return Err(Error::InvalidArgument("Moves per second (mps_lock) must be greater than zero".to_string()));
     }
     if moving.0 == 0 && moving.1 == 0 {
-        return Err("Movement(moving) must be greater than zero");
+        return Err(Error::InvalidArgument("Movement(moving) must be greater than zero".to_string()));
     }
 
     let tick_time_lock: Duration = Duration::from_secs_f64(1.0 / mps_lock as f64);
@@ -447,11 +460,11 @@ pub fn mouse_movement_complex(
         accumulation_y += d_y;
 
         if accumulation_x.abs() >= 1.0 {
-            mouse_movement(accumulation_x as i32, 0);
+            mouse_movement(backend, accumulation_x as i32, 0)?;
             accumulation_x -= accumulation_x.trunc();
         }
         if accumulation_y.abs() >= 1.0 {
-            mouse_movement(0, accumulation_y as i32);
+            mouse_movement(backend, 0, accumulation_y as i32)?;
             accumulation_y -= accumulation_y.trunc();
         }
 
@@ -469,7 +482,6 @@ pub fn mouse_movement_complex(
         duration.as_secs_f64(),
     );
     let (d_x, d_y) = (x_t - last_x, y_t - last_y);
-    mouse_movement((accumulation_x + d_x) as i32, (accumulation_y + d_y) as i32);
-    Ok(())
+    mouse_movement(backend, (accumulation_x + d_x) as i32, (accumulation_y + d_y) as i32)
 }
 
@@ -0,0 +1,360 @@
+use std::time::{Duration, Instant};
+
+use super::backend::{InputBackend, Key, MouseButton};
+use crate::error::Result;
+use crate::utils::DisplayArea;
+
+/// A single synthesized input event captured by [`RecordingBackend`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputEvent {
+    MoveAbsolute {
+        x: u32,
+        y: u32,
+        screen_width: u32,
+        screen_height: u32,
+    },
+    MoveVirtual {
+        x: i32,
+        y: i32,
+        desktop: DisplayArea,
+    },
+    MoveRelative {
+        dx: i32,
+        dy: i32,
+    },
+    ButtonDown(MouseButton),
+    ButtonUp(MouseButton),
+    Wheel(i32),
+    KeyDown(Key),
+    KeyUp(Key),
+    Text(String),
+}
+
+/// An [`InputEvent`] together with the time it was received, measured from the
+/// creation (or last [`RecordingBackend::clear`]) of the recorder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedEvent {
+    pub at: Duration,
+    pub event: InputEvent,
+}
+
+/// In-memory input backend that injects nothing and records every event.
+///
+/// # Behavior
+/// - Events are appended in call order with a monotonic timestamp
+/// - The cursor position is tracked from absolute and relative moves
+/// - Never fails, so it can drive `mouse`, `keyboard` and `action` flows on headless machines
+#[derive(Debug, Clone)]
+pub struct RecordingBackend {
+    start: Instant,
+    cursor: (i64, i64),
+    events: Vec<RecordedEvent>,
+}
+
+impl Default for RecordingBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RecordingBackend {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            cursor: (0, 0),
+            events: Vec::new(),
+        }
+    }
+
+    /// Recorded events in call order.
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Removes and returns the recorded events, keeping the cursor position.
+    pub fn take_events(&mut self) -> Vec<RecordedEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Drops the recorded events and restarts the timestamp clock.
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.start = Instant::now();
+    }
+
+    /// Cursor position implied by the recorded moves, starting from (0, 0).
+    pub fn cursor_position(&self) -> (i64, i64) {
+        self.cursor
+    }
+
+    /// Cursor positions after each absolute or relative move, in call order.
+    pub fn trajectory(&self) -> Vec<(i64, i64)> {
+        let mut position = (0, 0);
+        let mut points = Vec::new();
+        for recorded in &self.events {
+            match recorded.event {
+                InputEvent::MoveAbsolute { x, y, .. } => position = (x as i64, y as i64),
+                InputEvent::MoveVirtual { x, y, .. } => position = (x as i64, y as i64),
+                InputEvent::MoveRelative { dx, dy } => {
+                    position = (position.0 + dx as i64, position.1 + dy as i64)
+                }
+                _ => continue,
+            }
+            points.push(position);
+        }
+        points
+    }
+
+    /// Concatenation of all text typed through [`InputBackend::type_unicode_text`].
+    pub fn typed_text(&self) -> String {
+        self.events
+            .iter()
+            .filter_map(|recorded| match &recorded.event {
+                InputEvent::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn record(&mut self, event: InputEvent) {
+        self.events.push(RecordedEvent {
+            at: self.start.elapsed(),
+            event,
+        });
+    }
+}
+
+impl InputBackend for RecordingBackend {
+    fn mouse_move_absolute(
+        &mut self,
+        x: u32,
+        y: u32,
+        screen_width: u32,
+        screen_height: u32,
+    ) -> Result<()> {
+        self.cursor = (x as i64, y as i64);
+        self.record(InputEvent::MoveAbsolute {
+            x,
+            y,
+            screen_width,
+            screen_height,
+        });
+        Ok(())
+    }
+
+    fn mouse_move_virtual(&mut self, x: i32, y: i32, desktop: &DisplayArea) -> Result<()> {
+        self.cursor = (x as i64, y as i64);
+        self.record(InputEvent::MoveVirtual {
+            x,
+            y,
+            desktop: *desktop,
+        });
+        Ok(())
+    }
+
+    fn mouse_move_relative(&mut self, dx: i32, dy: i32) -> Result<()> {
+        self.cursor = (self.cursor.0 + dx as i64, self.cursor.1 + dy as i64);
+        self.record(InputEvent::MoveRelative { dx, dy });
+        Ok(())
+    }
+
+    fn mouse_button_down(&mut self, button: MouseButton) -> Result<()> {
+        self.record(InputEvent::ButtonDown(button));
+        Ok(())
+    }
+
+    fn mouse_button_up(&mut self, button: MouseButton) -> Result<()> {
+        self.record(InputEvent::ButtonUp(button));
+        Ok(())
+    }
+
+    fn mouse_wheel(&mut self, delta: i32) -> Result<()> {
+        self.record(InputEvent::Wheel(delta));
+        Ok(())
+    }
+
+    fn key_down(&mut self, key: Key) -> Result<()> {
+        self.record(InputEvent::KeyDown(key));
+        Ok(())
+    }
+
+    fn key_up(&mut self, key: Key) -> Result<()> {
+        self.record(InputEvent::KeyUp(key));
+        Ok(())
+    }
+
+    fn type_unicode_text(&mut self, text: &str) -> Result<()> {
+        self.record(InputEvent::Text(text.to_string()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::input::{keyboard, mouse};
+    use std::time::Duration;
+
+    fn relative_sum(events: &[RecordedEvent]) -> (i64, i64) {
+        events
+            .iter()
+            .fold((0, 0), |(x, y), recorded| match recorded.event {
+                InputEvent::MoveRelative { dx, dy } => (x + dx as i64, y + dy as i64),
+                _ => (x, y),
+            })
+    }
+
+    #[test]
+    fn set_position_complex_ends_at_end_pos() {
+        let mut backend = RecordingBackend::new();
+
+        mouse::mouse_set_position_complex(
+            &mut backend,
+            (100, 700),
+            (1500, 40),
+            (1920, 1080),
+            Duration::from_millis(30),
+            500,
+            &mouse::point_t_linear,
+        )
+        .unwrap();
+
+        assert!(backend.trajectory().len() > 1);
+        assert_eq!(
+            backend.events().last().unwrap().event,
+            InputEvent::MoveAbsolute {
+                x: 1500,
+                y: 40,
+                screen_width: 1920,
+                screen_height: 1080,
+            }
+        );
+        assert_eq!(backend.cursor_position(), (1500, 40));
+    }
+
+    #[test]
+    fn set_position_complex_rejects_a_zero_move() {
+        let mut backend = RecordingBackend::new();
+
+        let result = mouse::mouse_set_position_complex(
+            &mut backend,
+            (100, 100),
+            (100, 100),
+            (1920, 1080),
+            Duration::from_millis(30),
+            500,
+            &mouse::point_t_linear,
+        );
+
+        assert!(matches!(result, Err(Error::InvalidArgument(_))));
+        assert!(backend.events().is_empty());
+    }
+
+    #[test]
+    fn movement_complex_moves_exactly_by_moving() {
+        type MoveFunction = dyn Fn((f64, f64), (f64, f64), f64, f64) -> (f64, f64);
+        let move_fns: [&MoveFunction; 3] = [
+            &mouse::point_t_linear,
+            &|start, end, time_end, time_now| {
+                mouse::point_t_linear_acceleration(start, end, time_end, time_now, 900.0)
+            },
+            &|start, end, time_end, time_now| {
+                mouse::point_t_curved_acceleration(start, end, time_end, time_now, -700.0, 1300.0)
+            },
+        ];
+        for move_fn in move_fns {
+            let mut backend = RecordingBackend::new();
+
+            mouse::mouse_movement_complex(
+                &mut backend,
+                (333, 17),
+                Duration::from_millis(30),
+                500,
+                move_fn,
+            )
+            .unwrap();
+
+            assert_eq!(relative_sum(backend.events()), (333, 17));
+            assert_eq!(backend.cursor_position(), (333, 17));
+        }
+    }
+
+    #[test]
+    fn wheel_complex_sends_one_unit_per_tick() {
+        let mut backend = RecordingBackend::new();
+
+        mouse::mouse_wheel_complex(&mut backend, -5, Duration::from_millis(5)).unwrap();
+
+        let events: Vec<_> = backend.events().iter().map(|e| e.event.clone()).collect();
+        assert_eq!(events, vec![InputEvent::Wheel(-1); 5]);
+        assert!(matches!(
+            mouse::mouse_wheel_complex(&mut backend, 0, Duration::from_millis(5)),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn press_enter_presses_and_releases() {
+        let mut backend = RecordingBackend::new();
+
+        keyboard::press_enter(&mut backend).unwrap();
+
+        let events: Vec<_> = backend.events().iter().map(|e| e.event.clone()).collect();
+        assert_eq!(
+            events,
+            vec![
+                InputEvent::KeyDown(Key::Enter),
+                InputEvent::KeyUp(Key::Enter)
+            ]
+        );
+    }
+
+    #[test]
+    fn type_unicode_char_types_the_whole_code_point() {
+        let mut backend = RecordingBackend::new();
+
+        keyboard::type_unicode_char(&mut backend, 'ж').unwrap();
+        keyboard::type_unicode_char(&mut backend, '😀').unwrap();
+
+        assert_eq!(backend.typed_text(), "ж😀");
+    }
+
+    #[test]
+    fn set_position_virtual_reaches_monitors_at_negative_offsets() {
+        // Второй монитор слева от основного
+        let desktop = DisplayArea::from_points(-1280, -200, 1920, 1080);
+        let mut backend = RecordingBackend::new();
+
+        mouse::mouse_set_position_virtual(&mut backend, -1000, -150, &desktop).unwrap();
+
+        assert_eq!(
+            backend.events()[0].event,
+            InputEvent::MoveVirtual {
+                x: -1000,
+                y: -150,
+                desktop,
+            }
+        );
+        assert_eq!(backend.cursor_position(), (-1000, -150));
+    }
+
+    #[test]
+    fn set_position_virtual_rejects_points_outside_of_the_desktop() {
+        let desktop = DisplayArea::from_points(-1280, -200, 1920, 1080);
+        let mut backend = RecordingBackend::new();
+
+        for (x, y) in [(-1281, 0), (1920, 0), (0, -201), (0, 1080)] {
+            assert!(
+                matches!(
+                    mouse::mouse_set_position_virtual(&mut backend, x, y, &desktop),
+                    Err(Error::InvalidArgument(_))
+                ),
+                "({}, {})",
+                x,
+                y
+            );
+        }
+        assert!(backend.events().is_empty());
+    }
+}
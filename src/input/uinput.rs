@@ -0,0 +1,405 @@
+use std::ffi::CString;
+use std::io;
+use std::mem;
+use std::os::raw::{c_int, c_ulong};
+use std::thread;
+use std::time::Duration;
+
+use super::backend::{InputBackend, Key, MouseButton};
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+// linux/input-event-codes.h
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const EV_ABS: u16 = 0x03;
+const SYN_REPORT: u16 = 0;
+const REL_X: u16 = 0x00;
+const REL_Y: u16 = 0x01;
+const REL_WHEEL: u16 = 0x08;
+const REL_WHEEL_HI_RES: u16 = 0x0b;
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const BTN_LEFT: u16 = 0x110;
+const BTN_RIGHT: u16 = 0x111;
+const BTN_MIDDLE: u16 = 0x112;
+const BUS_VIRTUAL: u16 = 0x06;
+
+const KEY_ESC: u16 = 1;
+const KEY_BACKSPACE: u16 = 14;
+const KEY_TAB: u16 = 15;
+const KEY_ENTER: u16 = 28;
+const KEY_LEFTSHIFT: u16 = 42;
+const KEY_SPACE: u16 = 57;
+const KEY_DELETE: u16 = 111;
+/// Highest keycode registered on the virtual keyboard.
+const KEY_REGISTERED_MAX: u16 = 127;
+
+/// Wheel units per notch, matching `REL_WHEEL_HI_RES` resolution.
+const WHEEL_DELTA: i32 = 120;
+/// Upper bound of the ABS_X/ABS_Y axes of the virtual tablet.
+const ABS_MAX: i32 = 65535;
+/// Time for udev and the compositor to pick up newly created devices; events sent
+/// earlier are dropped.
+const DEVICE_SETTLE_TIME: Duration = Duration::from_millis(200);
+
+// linux/uinput.h
+const UINPUT_IOCTL_BASE: u32 = b'U' as u32;
+const UI_DEV_CREATE: libc::Ioctl = libc::_IO(UINPUT_IOCTL_BASE, 1);
+const UI_DEV_DESTROY: libc::Ioctl = libc::_IO(UINPUT_IOCTL_BASE, 2);
+const UI_DEV_SETUP: libc::Ioctl = libc::_IOW::<libc::uinput_setup>(UINPUT_IOCTL_BASE, 3);
+const UI_ABS_SETUP: libc::Ioctl = libc::_IOW::<libc::uinput_abs_setup>(UINPUT_IOCTL_BASE, 4);
+const UI_SET_EVBIT: libc::Ioctl = libc::_IOW::<c_int>(UINPUT_IOCTL_BASE, 100);
+const UI_SET_KEYBIT: libc::Ioctl = libc::_IOW::<c_int>(UINPUT_IOCTL_BASE, 101);
+const UI_SET_RELBIT: libc::Ioctl = libc::_IOW::<c_int>(UINPUT_IOCTL_BASE, 102);
+const UI_SET_ABSBIT: libc::Ioctl = libc::_IOW::<c_int>(UINPUT_IOCTL_BASE, 103);
+
+/// A single virtual device created through `/dev/uinput`.
+struct VirtualDevice {
+    fd: c_int,
+}
+
+impl VirtualDevice {
+    fn open(path: &str) -> io::Result<Self> {
+        let path = CString::new(path)?;
+        let fd = unsafe { libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_NONBLOCK) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { fd })
+    }
+
+    fn ioctl_int(&self, request: libc::Ioctl, value: u16) -> io::Result<()> {
+        if unsafe { libc::ioctl(self.fd, request, value as c_ulong) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn ioctl_ptr<T>(&self, request: libc::Ioctl, value: &T) -> io::Result<()> {
+        if unsafe { libc::ioctl(self.fd, request, value as *const T) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn enable(&self, event_type: u16, codes: impl IntoIterator<Item = u16>) -> io::Result<()> {
+        self.ioctl_int(UI_SET_EVBIT, event_type)?;
+        let request = match event_type {
+            EV_KEY => UI_SET_KEYBIT,
+            EV_REL => UI_SET_RELBIT,
+            _ => UI_SET_ABSBIT,
+        };
+        for code in codes {
+            self.ioctl_int(request, code)?;
+        }
+        Ok(())
+    }
+
+    fn setup_abs(&self, code: u16, maximum: i32) -> io::Result<()> {
+        let mut setup: libc::uinput_abs_setup = unsafe { mem::zeroed() };
+        setup.code = code;
+        setup.absinfo.minimum = 0;
+        setup.absinfo.maximum = maximum;
+        self.ioctl_ptr(UI_ABS_SETUP, &setup)
+    }
+
+    fn create(&self, name: &str, product: u16) -> io::Result<()> {
+        let mut setup: libc::uinput_setup = unsafe { mem::zeroed() };
+        setup.id.bustype = BUS_VIRTUAL;
+        setup.id.vendor = 0x1209;
+        setup.id.product = product;
+        setup.id.version = 1;
+        for (dst, src) in setup
+            .name
+            .iter_mut()
+            .zip(name.bytes().take(libc::UINPUT_MAX_NAME_SIZE - 1))
+        {
+            *dst = src as libc::c_char;
+        }
+        self.ioctl_ptr(UI_DEV_SETUP, &setup)?;
+        self.ioctl_int(UI_DEV_CREATE, 0)
+    }
+
+    fn emit(&self, event_type: u16, code: u16, value: i32) -> io::Result<()> {
+        let mut event: libc::input_event = unsafe { mem::zeroed() };
+        event.type_ = event_type;
+        event.code = code;
+        event.value = value;
+
+        let size = mem::size_of::<libc::input_event>();
+        let written = unsafe {
+            libc::write(
+                self.fd,
+                &event as *const libc::input_event as *const libc::c_void,
+                size,
+            )
+        };
+        if written != size as isize {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        self.emit(EV_SYN, SYN_REPORT, 0)
+    }
+}
+
+impl Drop for VirtualDevice {
+    fn drop(&mut self) {
+        unsafe {
+            libc::ioctl(self.fd, UI_DEV_DESTROY);
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// Input backend that injects events through virtual devices created with `/dev/uinput`.
+///
+/// Works below the display server, so it drives Wayland compositors and plain
+/// consoles where XTest is not available.
+///
+/// # Behavior
+/// - A relative mouse + keyboard device carries buttons, motion, wheel and keys
+/// - A separate ABS_X/ABS_Y tablet-style device carries absolute moves, mapped to
+///   the whole output by the compositor
+/// - Wheel deltas are sent as `REL_WHEEL_HI_RES` plus `REL_WHEEL` every 120 units
+/// - Text is typed with US QWERTY keycodes, so only ASCII characters are supported
+///
+/// # Permissions
+/// The process needs write access to `/dev/uinput` (root or the `input`/`uinput`
+/// group, depending on the distribution).
+pub struct UinputBackend {
+    pointer_keyboard: VirtualDevice,
+    tablet: VirtualDevice,
+    wheel_accumulator: i32,
+}
+
+impl UinputBackend {
+    /// Creates the virtual devices through `/dev/uinput`.
+    pub fn new() -> Result<Self> {
+        Self::with_device_path("/dev/uinput")
+    }
+
+    /// Creates the virtual devices through the uinput node at `path`.
+    ///
+    /// Blocks for 200 ms while the devices settle, events sent earlier are lost.
+    pub fn with_device_path(path: &str) -> Result<Self> {
+        let pointer_keyboard = VirtualDevice::open(path).map_err(|e| {
+            Error::InputFailed(format!("UinputBackend: can not open {}: {}", path, e))
+        })?;
+        pointer_keyboard.enable(
+            EV_KEY,
+            (1..=KEY_REGISTERED_MAX).chain([BTN_LEFT, BTN_RIGHT, BTN_MIDDLE]),
+        )?;
+        pointer_keyboard.enable(EV_REL, [REL_X, REL_Y, REL_WHEEL, REL_WHEEL_HI_RES])?;
+        pointer_keyboard.create("mavis virtual mouse and keyboard", 0x0001)?;
+
+        let tablet = VirtualDevice::open(path).map_err(|e| {
+            Error::InputFailed(format!("UinputBackend: can not open {}: {}", path, e))
+        })?;
+        tablet.enable(EV_KEY, [BTN_LEFT, BTN_RIGHT, BTN_MIDDLE])?;
+        tablet.enable(EV_ABS, [ABS_X, ABS_Y])?;
+        tablet.setup_abs(ABS_X, ABS_MAX)?;
+        tablet.setup_abs(ABS_Y, ABS_MAX)?;
+        tablet.create("mavis virtual tablet", 0x0002)?;
+
+        thread::sleep(DEVICE_SETTLE_TIME);
+
+        Ok(Self {
+            pointer_keyboard,
+            tablet,
+            wheel_accumulator: 0,
+        })
+    }
+
+    fn key_event(&mut self, code: u16, value: i32) -> Result<()> {
+        self.pointer_keyboard.emit(EV_KEY, code, value)?;
+        self.pointer_keyboard.sync()?;
+        Ok(())
+    }
+
+    fn tap(&mut self, code: u16, shifted: bool) -> Result<()> {
+        if shifted {
+            self.key_event(KEY_LEFTSHIFT, 1)?;
+        }
+        self.key_event(code, 1)?;
+        self.key_event(code, 0)?;
+        if shifted {
+            self.key_event(KEY_LEFTSHIFT, 0)?;
+        }
+        Ok(())
+    }
+}
+
+fn key_code(key: Key) -> u16 {
+    match key {
+        Key::Enter => KEY_ENTER,
+        Key::Tab => KEY_TAB,
+        Key::Escape => KEY_ESC,
+        Key::Backspace => KEY_BACKSPACE,
+        Key::Delete => KEY_DELETE,
+    }
+}
+
+fn button_code(button: MouseButton) -> u16 {
+    match button {
+        MouseButton::Left => BTN_LEFT,
+        MouseButton::Right => BTN_RIGHT,
+        MouseButton::Middle => BTN_MIDDLE,
+    }
+}
+
+/// Maps an ASCII character to its US QWERTY keycode and shift state.
+fn char_to_key(c: char) -> Option<(u16, bool)> {
+    const LETTERS: &[u16; 26] = &[
+        30, 48, 46, 32, 18, 33, 34, 35, 23, 36, 37, 38, 50, 49, 24, 25, 16, 19, 31, 20, 22, 47, 17,
+        45, 21, 44,
+    ];
+    const DIGITS: &[u16; 10] = &[11, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+
+    let key = match c {
+        'a'..='z' => (LETTERS[(c as u8 - b'a') as usize], false),
+        'A'..='Z' => (LETTERS[(c as u8 - b'A') as usize], true),
+        '0'..='9' => (DIGITS[(c as u8 - b'0') as usize], false),
+        ')' => (11, true),
+        '!' => (2, true),
+        '@' => (3, true),
+        '#' => (4, true),
+        '$' => (5, true),
+        '%' => (6, true),
+        '^' => (7, true),
+        '&' => (8, true),
+        '*' => (9, true),
+        '(' => (10, true),
+        '-' => (12, false),
+        '_' => (12, true),
+        '=' => (13, false),
+        '+' => (13, true),
+        '[' => (26, false),
+        '{' => (26, true),
+        ']' => (27, false),
+        '}' => (27, true),
+        ';' => (39, false),
+        ':' => (39, true),
+        '\'' => (40, false),
+        '"' => (40, true),
+        '`' => (41, false),
+        '~' => (41, true),
+        '\\' => (43, false),
+        '|' => (43, true),
+        ',' => (51, false),
+        '<' => (51, true),
+        '.' => (52, false),
+        '>' => (52, true),
+        '/' => (53, false),
+        '?' => (53, true),
+        ' ' => (KEY_SPACE, false),
+        '\t' => (KEY_TAB, false),
+        '\n' | '\r' => (KEY_ENTER, false),
+        _ => return None,
+    };
+    Some(key)
+}
+
+impl InputBackend for UinputBackend {
+    fn mouse_move_absolute(
+        &mut self,
+        x: u32,
+        y: u32,
+        screen_width: u32,
+        screen_height: u32,
+    ) -> Result<()> {
+        if screen_width == 0 || screen_height == 0 {
+            return Err(Error::InvalidArgument(
+                "Screen dimensions must be greater than zero".to_string(),
+            ));
+        }
+        let abs_x = (x as i64 * ABS_MAX as i64 / screen_width as i64) as i32;
+        let abs_y = (y as i64 * ABS_MAX as i64 / screen_height as i64) as i32;
+        self.tablet.emit(EV_ABS, ABS_X, abs_x)?;
+        self.tablet.emit(EV_ABS, ABS_Y, abs_y)?;
+        self.tablet.sync()?;
+        Ok(())
+    }
+
+    /// The tablet axes are mapped by the compositor onto the whole desktop, so the
+    /// position is normalized against `desktop`.
+    fn mouse_move_virtual(&mut self, x: i32, y: i32, desktop: &DisplayArea) -> Result<()> {
+        let ((left, top), (width, height)) = desktop.get_rectangle();
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidArgument(
+                "Desktop dimensions must be greater than zero".to_string(),
+            ));
+        }
+        let abs_x = ((x - left) as i64 * ABS_MAX as i64 / width as i64) as i32;
+        let abs_y = ((y - top) as i64 * ABS_MAX as i64 / height as i64) as i32;
+        self.tablet.emit(EV_ABS, ABS_X, abs_x)?;
+        self.tablet.emit(EV_ABS, ABS_Y, abs_y)?;
+        self.tablet.sync()?;
+        Ok(())
+    }
+
+    fn mouse_move_relative(&mut self, dx: i32, dy: i32) -> Result<()> {
+        if dx != 0 {
+            self.pointer_keyboard.emit(EV_REL, REL_X, dx)?;
+        }
+        if dy != 0 {
+            self.pointer_keyboard.emit(EV_REL, REL_Y, dy)?;
+        }
+        self.pointer_keyboard.sync()?;
+        Ok(())
+    }
+
+    fn mouse_button_down(&mut self, button: MouseButton) -> Result<()> {
+        self.key_event(button_code(button), 1)
+    }
+
+    fn mouse_button_up(&mut self, button: MouseButton) -> Result<()> {
+        self.key_event(button_code(button), 0)
+    }
+
+    fn mouse_wheel(&mut self, delta: i32) -> Result<()> {
+        self.pointer_keyboard
+            .emit(EV_REL, REL_WHEEL_HI_RES, delta)?;
+        self.wheel_accumulator += delta;
+        let notches = self.wheel_accumulator / WHEEL_DELTA;
+        if notches != 0 {
+            self.pointer_keyboard.emit(EV_REL, REL_WHEEL, notches)?;
+            self.wheel_accumulator -= notches * WHEEL_DELTA;
+        }
+        self.pointer_keyboard.sync()?;
+        Ok(())
+    }
+
+    fn key_down(&mut self, key: Key) -> Result<()> {
+        self.key_event(key_code(key), 1)
+    }
+
+    fn key_up(&mut self, key: Key) -> Result<()> {
+        self.key_event(key_code(key), 0)
+    }
+
+    /// Nothing is typed unless every character of `text` has a key, so an
+    /// unsupported character never leaves a half-typed prefix behind.
+    fn type_unicode_text(&mut self, text: &str) -> Result<()> {
+        let keys = text
+            .chars()
+            .map(|c| {
+                char_to_key(c).ok_or_else(|| {
+                    Error::InputFailed(format!(
+                        "UinputBackend: character {:?} has no key in the US layout",
+                        c
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for (code, shifted) in keys {
+            self.tap(code, shifted)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+INPUT, INPUT_0, INPUT_KEYBOARD, KEYBD_EVENT_FLAGS, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
+SendInput, VIRTUAL_KEY, VK_BACK, VK_DELETE, VK_ESCAPE, VK_RETURN, VK_TAB,
+};
+use winapi::shared::minwindef::DWORD;
+use winapi::um::winuser::{
+MOUSEEVENTF_ABSOLUTE, MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP, MOUSEEVENTF_MIDDLEDOWN,
+MOUSEEVENTF_MIDDLEUP, MOUSEEVENTF_MOVE, MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP,
+MOUSEEVENTF_VIRTUALDESK, MOUSEEVENTF_WHEEL, mouse_event,
+};
+
+use super::backend::{InputBackend, Key, MouseButton};
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+/// Input backend built on the WinAPI `mouse_event` and `SendInput` functions.
+///
+/// # Safety
+/// All methods contain unsafe WinAPI calls. The caller must ensure:
+/// - The thread has permission to synthesize input
+/// - No other thread is manipulating mouse or keyboard state concurrently
+/// - The target window is prepared to receive input
+///
+/// # Windows Notes
+/// - Absolute moves are normalized to the 0-65535 range (`MOUSEEVENTF_ABSOLUTE`)
+/// - Virtual desktop moves add `MOUSEEVENTF_VIRTUALDESK`, so the range covers all monitors
+/// - Button and wheel events use the current cursor position (0,0 coordinates)
+/// - Text uses `KEYEVENTF_UNICODE`, two events per character
+/// - Input may be blocked by UIPI (User Interface Privilege Isolation)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WinApiBackend;
+
+impl WinApiBackend {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn virtual_key(key: Key) -> VIRTUAL_KEY {
+    match key {
+        Key::Enter => VK_RETURN,
+        Key::Tab => VK_TAB,
+        Key::Escape => VK_ESCAPE,
+        Key::Backspace => VK_BACK,
+        Key::Delete => VK_DELETE,
+    }
+}
+
+fn keyboard_input(w_vk: VIRTUAL_KEY, w_scan: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: w_vk,
+                wScan: w_scan,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
+}
+
+fn send_inputs(inputs: &[INPUT]) -> Result<()> {
+    let sent = unsafe { SendInput(inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent as usize != inputs.len() {
+        return Err(Error::InputFailed(format!(
+            "SendInput: only {} of {} events were injected",
+            sent,
+            inputs.len()
+        )));
+    }
+    Ok(())
+}
+
+impl InputBackend for WinApiBackend {
+    fn mouse_move_absolute(
+        &mut self,
+        x: u32,
+        y: u32,
+        screen_width: u32,
+        screen_height: u32,
+    ) -> Result<()> {
+        if screen_width == 0 || screen_height == 0 {
+            return Err(Error::InvalidArgument(
+                "Screen dimensions must be greater than zero".to_string(),
+            ));
+        }
+        unsafe {
+            mouse_event(
+                MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE,
+                (x * 65536 / screen_width) as DWORD,
+                (y * 65536 / screen_height) as DWORD,
+                0,
+                0,
+            );
+        }
+        Ok(())
+    }
+
+    fn mouse_move_virtual(&mut self, x: i32, y: i32, desktop: &DisplayArea) -> Result<()> {
+        let ((left, top), (width, height)) = desktop.get_rectangle();
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidArgument(
+                "Desktop dimensions must be greater than zero".to_string(),
+            ));
+        }
+        let x = (x - left) as i64 * 65536 / width as i64;
+        let y = (y - top) as i64 * 65536 / height as i64;
+        unsafe {
+            mouse_event(
+                MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE | MOUSEEVENTF_VIRTUALDESK,
+                x as DWORD,
+                y as DWORD,
+                0,
+                0,
+            );
+        }
+        Ok(())
+    }
+
+    fn mouse_move_relative(&mut self, dx: i32, dy: i32) -> Result<()> {
+        unsafe {
+            mouse_event(MOUSEEVENTF_MOVE, dx as DWORD, dy as DWORD, 0, 0);
+        }
+        Ok(())
+    }
+
+    fn mouse_button_down(&mut self, button: MouseButton) -> Result<()> {
+        let flag = match button {
+            MouseButton::Left => MOUSEEVENTF_LEFTDOWN,
+            MouseButton::Right => MOUSEEVENTF_RIGHTDOWN,
+            MouseButton::Middle => MOUSEEVENTF_MIDDLEDOWN,
+        };
+        unsafe {
+            mouse_event(flag, 0, 0, 0, 0);
+        }
+        Ok(())
+    }
+
+    fn mouse_button_up(&mut self, button: MouseButton) -> Result<()> {
+        let flag = match button {
+            MouseButton::Left => MOUSEEVENTF_LEFTUP,
+            MouseButton::Right => MOUSEEVENTF_RIGHTUP,
+            MouseButton::Middle => MOUSEEVENTF_MIDDLEUP,
+        };
+        unsafe {
+            mouse_event(flag, 0, 0, 0, 0);
+        }
+        Ok(())
+    }
+
+    fn mouse_wheel(&mut self, delta: i32) -> Result<()> {
+        unsafe {
+            mouse_event(MOUSEEVENTF_WHEEL, 0, 0, delta as DWORD, 0);
+        }
+        Ok(())
+    }
+
+    fn key_down(&mut self, key: Key) -> Result<()> {
+        send_inputs(&[keyboard_input(virtual_key(key), 0, KEYBD_EVENT_FLAGS(0))])
+    }
+
+    fn key_up(&mut self, key: Key) -> Result<()> {
+        send_inputs(&[keyboard_input(virtual_key(key), 0, KEYEVENTF_KEYUP)])
+    }
+
+    fn type_unicode_text(&mut self, text: &str) -> Result<()> {
+        let mut inputs = Vec::with_capacity(text.len() * 2);
+
+        // Символы вне BMP уходят суррогатной парой: по нажатию на каждую половину
+        let mut buf = [0u16; 2];
+        for c in text.chars() {
+            for &unit in c.encode_utf16(&mut buf).iter() {
+                inputs.push(keyboard_input(VIRTUAL_KEY(0), unit, KEYEVENTF_UNICODE));
+                inputs.push(keyboard_input(
+                    VIRTUAL_KEY(0),
+                    unit,
+                    KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
+                ));
+            }
+        }
+
+        if inputs.is_empty() {
+            return Ok(());
+        }
+        send_inputs(&inputs)
+    }
+}
@@ -0,0 +1,285 @@
+use std::ffi::CString;
+use std::os::raw::{c_int, c_uint};
+use std::ptr;
+use x11::{keysym, xlib, xtest};
+
+use super::backend::{InputBackend, Key, MouseButton};
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+use crate::xerror::trap_errors;
+
+/// Wheel units per X11 button 4/5 click (one notch).
+const WHEEL_DELTA: i32 = 120;
+
+/// Input backend for X11 desktops built on the XTest extension.
+///
+/// Works with any X server that has XTest enabled, including Xvfb, which makes it
+/// usable for end-to-end runs of the `action` functions on headless machines.
+///
+/// # Behavior
+/// - Absolute moves use root window pixels, the screen size arguments are ignored
+/// - Wheel deltas are accumulated and sent as button 4/5 clicks every 120 units
+/// - Characters that have no key in the current layout are typed through a
+///   temporarily remapped spare keycode
+pub struct XTestBackend {
+    display: *mut xlib::Display,
+    scratch_keycode: Option<u8>,
+    wheel_accumulator: i32,
+}
+
+impl XTestBackend {
+    /// Connects to the display named by the `DISPLAY` environment variable.
+    pub fn open() -> Result<Self> {
+        Self::connect(ptr::null())
+    }
+
+    /// Connects to the display `name`, e.g. `":99"` for an Xvfb server.
+    pub fn open_display(name: &str) -> Result<Self> {
+        let name = CString::new(name)
+            .map_err(|e| Error::InvalidArgument(format!("display name: {}", e)))?;
+        Self::connect(name.as_ptr())
+    }
+
+    fn connect(name: *const std::os::raw::c_char) -> Result<Self> {
+        let display = unsafe { xlib::XOpenDisplay(name) };
+        if display.is_null() {
+            return Err(Error::InputFailed(
+                "XTestBackend: can not open X display".to_string(),
+            ));
+        }
+
+        let (mut event_base, mut error_base, mut major, mut minor) = (0, 0, 0, 0);
+        let has_xtest = unsafe {
+            xtest::XTestQueryExtension(
+                display,
+                &mut event_base,
+                &mut error_base,
+                &mut major,
+                &mut minor,
+            )
+        };
+        if has_xtest == 0 {
+            unsafe { xlib::XCloseDisplay(display) };
+            return Err(Error::InputFailed(
+                "XTestBackend: XTest extension is not available".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            display,
+            scratch_keycode: unsafe { find_scratch_keycode(display) },
+            wheel_accumulator: 0,
+        })
+    }
+
+    /// Sends the request of `send` and waits until the X server has processed it.
+    ///
+    /// A rejected request, e.g. a keycode out of range, is reported as an error
+    /// instead of terminating the process through the default Xlib error handler.
+    fn send(&self, what: &str, send: impl FnOnce(*mut xlib::Display) -> c_int) -> Result<()> {
+        let status = unsafe { trap_errors(self.display, || send(self.display)) }
+            .map_err(|e| Error::InputFailed(format!("XTestBackend: {} failed: {}", what, e)))?;
+        if status == 0 {
+            return Err(Error::InputFailed(format!(
+                "XTestBackend: {} was rejected by the X server",
+                what
+            )));
+        }
+        Ok(())
+    }
+
+    fn keycode(&self, key: Key) -> Result<u8> {
+        match unsafe { xlib::XKeysymToKeycode(self.display, key_to_keysym(key)) } {
+            0 => Err(Error::InputFailed(format!(
+                "XTestBackend: {:?} is not mapped to any keycode",
+                key
+            ))),
+            keycode => Ok(keycode),
+        }
+    }
+
+    fn fake_key(&mut self, keycode: u8, is_press: bool) -> Result<()> {
+        self.send("key event", |display| unsafe {
+            xtest::XTestFakeKeyEvent(display, keycode as c_uint, is_press as c_int, 0)
+        })
+    }
+
+    fn fake_button(&mut self, button: c_uint, is_press: bool) -> Result<()> {
+        self.send("button event", |display| unsafe {
+            xtest::XTestFakeButtonEvent(display, button, is_press as c_int, 0)
+        })
+    }
+
+    fn tap_keycode(&mut self, keycode: u8, shifted: bool) -> Result<()> {
+        let shift =
+            unsafe { xlib::XKeysymToKeycode(self.display, keysym::XK_Shift_L as xlib::KeySym) };
+        if shifted {
+            self.fake_key(shift, true)?;
+        }
+        self.fake_key(keycode, true)?;
+        self.fake_key(keycode, false)?;
+        if shifted {
+            self.fake_key(shift, false)?;
+        }
+        Ok(())
+    }
+
+    fn tap_keysym(&mut self, sym: xlib::KeySym) -> Result<()> {
+        let keycode = unsafe { xlib::XKeysymToKeycode(self.display, sym) };
+        if keycode != 0 {
+            if unsafe { xlib::XKeycodeToKeysym(self.display, keycode, 0) } == sym {
+                return self.tap_keycode(keycode, false);
+            }
+            if unsafe { xlib::XKeycodeToKeysym(self.display, keycode, 1) } == sym {
+                return self.tap_keycode(keycode, true);
+            }
+        }
+
+        // Символа нет в раскладке: временно назначаем его свободной клавише
+        let scratch = self.scratch_keycode.ok_or_else(|| {
+            Error::InputFailed(
+                "XTestBackend: no spare keycode to type a character outside of the layout"
+                    .to_string(),
+            )
+        })?;
+        let mut mapping = [sym, sym];
+        self.send("keyboard remapping", |display| unsafe {
+            xlib::XChangeKeyboardMapping(display, scratch as c_int, 2, mapping.as_mut_ptr(), 1);
+            1
+        })?;
+        let result = self.tap_keycode(scratch, false);
+        let mut empty = [xlib::NoSymbol as xlib::KeySym; 2];
+        let restored = self.send("keyboard remapping", |display| unsafe {
+            xlib::XChangeKeyboardMapping(display, scratch as c_int, 2, empty.as_mut_ptr(), 1);
+            1
+        });
+        result.and(restored)
+    }
+}
+
+impl Drop for XTestBackend {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XCloseDisplay(self.display);
+        }
+    }
+}
+
+/// Finds a keycode without any keysym bound to it.
+unsafe fn find_scratch_keycode(display: *mut xlib::Display) -> Option<u8> {
+    let (mut min_keycode, mut max_keycode) = (0, 0);
+    let mut syms_per_code = 0;
+    unsafe {
+        xlib::XDisplayKeycodes(display, &mut min_keycode, &mut max_keycode);
+        let count = max_keycode - min_keycode + 1;
+        let mapping =
+            xlib::XGetKeyboardMapping(display, min_keycode as u8, count, &mut syms_per_code);
+        if mapping.is_null() {
+            return None;
+        }
+
+        let syms = std::slice::from_raw_parts(mapping, (count * syms_per_code) as usize);
+        let free = syms
+            .chunks(syms_per_code as usize)
+            .position(|code_syms| code_syms.iter().all(|&sym| sym == 0))
+            .map(|index| (min_keycode + index as c_int) as u8);
+        xlib::XFree(mapping as *mut _);
+        free
+    }
+}
+
+fn key_to_keysym(key: Key) -> xlib::KeySym {
+    (match key {
+        Key::Enter => keysym::XK_Return,
+        Key::Tab => keysym::XK_Tab,
+        Key::Escape => keysym::XK_Escape,
+        Key::Backspace => keysym::XK_BackSpace,
+        Key::Delete => keysym::XK_Delete,
+    }) as xlib::KeySym
+}
+
+fn char_to_keysym(c: char) -> xlib::KeySym {
+    match c {
+        '\n' | '\r' => keysym::XK_Return as xlib::KeySym,
+        '\t' => keysym::XK_Tab as xlib::KeySym,
+        // Latin-1 совпадает с кодами keysym, остальное — Unicode keysym
+        '\u{20}'..='\u{7e}' | '\u{a0}'..='\u{ff}' => c as xlib::KeySym,
+        _ => 0x0100_0000 | c as xlib::KeySym,
+    }
+}
+
+fn x_button(button: MouseButton) -> c_uint {
+    match button {
+        MouseButton::Left => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Right => 3,
+    }
+}
+
+impl InputBackend for XTestBackend {
+    fn mouse_move_absolute(
+        &mut self,
+        x: u32,
+        y: u32,
+        _screen_width: u32,
+        _screen_height: u32,
+    ) -> Result<()> {
+        self.send("motion event", |display| unsafe {
+            xtest::XTestFakeMotionEvent(display, -1, x as c_int, y as c_int, 0)
+        })
+    }
+
+    /// Root window coordinates already span every monitor (Xinerama/XRandR),
+    /// so `desktop` is not needed.
+    fn mouse_move_virtual(&mut self, x: i32, y: i32, _desktop: &DisplayArea) -> Result<()> {
+        self.send("motion event", |display| unsafe {
+            xtest::XTestFakeMotionEvent(display, -1, x, y, 0)
+        })
+    }
+
+    fn mouse_move_relative(&mut self, dx: i32, dy: i32) -> Result<()> {
+        self.send("relative motion event", |display| unsafe {
+            xtest::XTestFakeRelativeMotionEvent(display, -1, dx, dy, 0)
+        })
+    }
+
+    fn mouse_button_down(&mut self, button: MouseButton) -> Result<()> {
+        self.fake_button(x_button(button), true)
+    }
+
+    fn mouse_button_up(&mut self, button: MouseButton) -> Result<()> {
+        self.fake_button(x_button(button), false)
+    }
+
+    fn mouse_wheel(&mut self, delta: i32) -> Result<()> {
+        self.wheel_accumulator += delta;
+        while self.wheel_accumulator.abs() >= WHEEL_DELTA {
+            let (button, step) = if self.wheel_accumulator > 0 {
+                (4, WHEEL_DELTA)
+            } else {
+                (5, -WHEEL_DELTA)
+            };
+            self.fake_button(button, true)?;
+            self.fake_button(button, false)?;
+            self.wheel_accumulator -= step;
+        }
+        Ok(())
+    }
+
+    fn key_down(&mut self, key: Key) -> Result<()> {
+        let keycode = self.keycode(key)?;
+        self.fake_key(keycode, true)
+    }
+
+    fn key_up(&mut self, key: Key) -> Result<()> {
+        let keycode = self.keycode(key)?;
+        self.fake_key(keycode, false)
+    }
+
+    fn type_unicode_text(&mut self, text: &str) -> Result<()> {
+        for c in text.chars() {
+            self.tap_keysym(char_to_keysym(c))?;
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,12 @@
 pub mod action;
+pub mod automator;
+pub mod capture;
+pub mod display;
+pub mod error;
 pub mod input;
 pub mod utils;
 pub mod vision;
+#[cfg(all(target_os = "linux", feature = "x11"))]
+mod xerror;
+
+pub use error::{Error, Result};
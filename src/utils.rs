@@ -1,6 +1,20 @@
-use opencv::{core, imgproc, prelude::*};
-use screenshots;
+use opencv::core::Rect;
 
+/// Point of a [`DisplayArea`] returned by [`DisplayArea::anchor_point`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnchorPoint {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    CenterLeft,
+    Center,
+    CenterRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct DisplayArea {
     s_x: i32,
     s_y: i32,
@@ -60,51 +74,254 @@ impl DisplayArea {
     pub fn get_average_point(&self) -> (i32, i32) {
         ((self.s_x + self.e_x) / 2, (self.s_y + self.e_y) / 2)
     }
+
+    pub fn width(&self) -> u32 {
+        (self.e_x - self.s_x).unsigned_abs()
+    }
+
+    pub fn height(&self) -> u32 {
+        (self.e_y - self.s_y).unsigned_abs()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width() == 0 || self.height() == 0
+    }
+
+    /// Whether the point lies inside the area; the right and bottom edges are excluded.
+    pub fn contains_point(&self, point: (i32, i32)) -> bool {
+        let ((s_x, s_y), (e_x, e_y)) = self.normalized();
+        (s_x..e_x).contains(&point.0) && (s_y..e_y).contains(&point.1)
+    }
+
+    /// Whether `other` lies entirely inside the area.
+    pub fn contains(&self, other: &DisplayArea) -> bool {
+        let ((s_x, s_y), (e_x, e_y)) = self.normalized();
+        let ((o_sx, o_sy), (o_ex, o_ey)) = other.normalized();
+        s_x <= o_sx && s_y <= o_sy && o_ex <= e_x && o_ey <= e_y
+    }
+
+    /// Overlapping part of both areas, `None` if they do not overlap.
+    pub fn intersection(&self, other: &DisplayArea) -> Option<Self> {
+        let ((s_x, s_y), (e_x, e_y)) = self.normalized();
+        let ((o_sx, o_sy), (o_ex, o_ey)) = other.normalized();
+
+        let (x0, y0) = (s_x.max(o_sx), s_y.max(o_sy));
+        let (x1, y1) = (e_x.min(o_ex), e_y.min(o_ey));
+        if x0 >= x1 || y0 >= y1 {
+            return None;
+        }
+        Some(Self::from_points(x0, y0, x1, y1))
+    }
+
+    /// Smallest area containing both areas.
+    pub fn union(&self, other: &DisplayArea) -> Self {
+        let ((s_x, s_y), (e_x, e_y)) = self.normalized();
+        let ((o_sx, o_sy), (o_ex, o_ey)) = other.normalized();
+        Self::from_points(s_x.min(o_sx), s_y.min(o_sy), e_x.max(o_ex), e_y.max(o_ey))
+    }
+
+    /// Intersection over union, `0.0` for disjoint areas and `1.0` for equal ones.
+    pub fn iou(&self, other: &DisplayArea) -> f64 {
+        let intersection = self
+            .intersection(other)
+            .map_or(0.0, |area| area.width() as f64 * area.height() as f64);
+        let union = self.width() as f64 * self.height() as f64
+            + other.width() as f64 * other.height() as f64
+            - intersection;
+        if union <= 0.0 {
+            return 0.0;
+        }
+        intersection / union
+    }
+
+    /// Grows the area by `dx` on the left and right and by `dy` on the top and bottom.
+    pub fn inflate(&self, dx: i32, dy: i32) -> Self {
+        let ((s_x, s_y), (e_x, e_y)) = self.normalized();
+        Self::from_points(s_x - dx, s_y - dy, e_x + dx, e_y + dy)
+    }
+
+    /// Shrinks the area by `dx` on the left and right and by `dy` on the top and bottom.
+    ///
+    /// An area smaller than the margins collapses to its center.
+    pub fn deflate(&self, dx: i32, dy: i32) -> Self {
+        let ((s_x, s_y), (e_x, e_y)) = self.normalized();
+        let (c_x, c_y) = self.get_average_point();
+        Self::from_points(
+            (s_x + dx).min(c_x),
+            (s_y + dy).min(c_y),
+            (e_x - dx).max(c_x),
+            (e_y - dy).max(c_y),
+        )
+    }
+
+    /// Splits the area into `rows` x `cols` cells, row by row.
+    ///
+    /// The remainder pixels go to the last row and column.
+    pub fn split_grid(&self, rows: u32, cols: u32) -> Vec<Self> {
+        if rows == 0 || cols == 0 {
+            return Vec::new();
+        }
+
+        let ((s_x, s_y), _) = self.normalized();
+        let (cell_w, cell_h) = (self.width() / cols, self.height() / rows);
+        let mut cells = Vec::with_capacity((rows * cols) as usize);
+        for row in 0..rows {
+            for col in 0..cols {
+                let w = if col + 1 == cols { self.width() - cell_w * col } else { cell_w };
+                let h = if row + 1 == rows { self.height() - cell_h * row } else { cell_h };
+                cells.push(Self::from_rectangle(
+                    s_x + (cell_w * col) as i32,
+                    s_y + (cell_h * row) as i32,
+                    w,
+                    h,
+                ));
+            }
+        }
+        cells
+    }
+
+    /// Moves every edge inside `bounds`, e.g. the screen area.
+    ///
+    /// Unlike [`DisplayArea::intersection`] the result is always an area; it is
+    /// empty when the two do not overlap.
+    pub fn clamp_to(&self, bounds: &DisplayArea) -> Self {
+        let ((s_x, s_y), (e_x, e_y)) = self.normalized();
+        let ((b_sx, b_sy), (b_ex, b_ey)) = bounds.normalized();
+        Self::from_points(
+            s_x.clamp(b_sx, b_ex),
+            s_y.clamp(b_sy, b_ey),
+            e_x.clamp(b_sx, b_ex),
+            e_y.clamp(b_sy, b_ey),
+        )
+    }
+
+    pub fn anchor_point(&self, point: AnchorPoint) -> (i32, i32) {
+        let ((s_x, s_y), (e_x, e_y)) = self.normalized();
+        let (c_x, c_y) = self.get_average_point();
+        match point {
+            AnchorPoint::TopLeft => (s_x, s_y),
+            AnchorPoint::TopCenter => (c_x, s_y),
+            AnchorPoint::TopRight => (e_x, s_y),
+            AnchorPoint::CenterLeft => (s_x, c_y),
+            AnchorPoint::Center => (c_x, c_y),
+            AnchorPoint::CenterRight => (e_x, c_y),
+            AnchorPoint::BottomLeft => (s_x, e_y),
+            AnchorPoint::BottomCenter => (c_x, e_y),
+            AnchorPoint::BottomRight => (e_x, e_y),
+        }
+    }
+
+    fn normalized(&self) -> ((i32, i32), (i32, i32)) {
+        (
+            (self.s_x.min(self.e_x), self.s_y.min(self.e_y)),
+            (self.s_x.max(self.e_x), self.s_y.max(self.e_y)),
+        )
+    }
 }
 
-/// Captures a screen area and converts it to an OpenCV Mat in BGR format.
-///
-/// # Arguments
-/// * `start_pos` - (x, y) coordinates of the capture area's top-left corner
-/// * `end_pos` - (width, height) dimensions of the capture area from start_pos
-///
-/// # Returns
-/// `Result<core::Mat, Box<dyn Error>>` containing:
-/// - Success: BGR formatted OpenCV Mat (8UC3)
-/// - Error: If capture or conversion fails
-///
-/// # Behavior
-/// - Captures from primary screen only (first screen in list)
-/// - Converts RGBA screenshot to OpenCV BGR format
-/// - Preserves original image dimensions
-/// - Uses unsafe blocks for Mat creation and data copying
-///
-/// # Safety
-/// - Unsafe operations used for Mat creation and data copying
-/// - Caller must ensure valid screen coordinates
-pub fn screenshot_area_to_mat(area: &DisplayArea) -> Result<core::Mat, Box<dyn std::error::Error>> {
-    let screens = screenshots::Screen::all()?;
-    let screen = &screens[0];
-    let ((x, y), (width, height)) = area.get_rectangle();
-    let image = screen.capture_area(x, y, width, height)?;
-    let (width, height) = (image.width() as i32, image.height() as i32);
-
-    let mat = unsafe {
-        let mut mat = core::Mat::new_rows_cols(height, width, core::CV_8UC4)?;
-        core::Mat::from_slice(image.as_raw())?
-            .reshape(4, height)?
-            .copy_to(&mut mat)?;
-        mat
-    };
-
-    let mut bgr_mat = core::Mat::default();
-    imgproc::cvt_color(
-        &mat,
-        &mut bgr_mat,
-        imgproc::COLOR_RGBA2BGR,
-        0,
-        core::AlgorithmHint::ALGO_HINT_DEFAULT,
-    )?;
-
-    Ok(bgr_mat)
+impl From<Rect> for DisplayArea {
+    fn from(rect: Rect) -> Self {
+        Self::from_points(rect.x, rect.y, rect.x + rect.width, rect.y + rect.height)
+    }
+}
+
+impl From<DisplayArea> for Rect {
+    fn from(area: DisplayArea) -> Self {
+        let ((x, y), (width, height)) = area.get_rectangle();
+        Rect::new(x, y, width as i32, height as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(x: i32, y: i32, w: u32, h: u32) -> DisplayArea {
+        DisplayArea::from_rectangle(x, y, w, h)
+    }
+
+    #[test]
+    fn intersection_of_overlapping_areas() {
+        assert_eq!(
+            area(0, 0, 100, 50).intersection(&area(60, 20, 100, 100)),
+            Some(area(60, 20, 40, 30))
+        );
+        assert_eq!(
+            area(10, 10, 20, 20).intersection(&area(0, 0, 100, 100)),
+            Some(area(10, 10, 20, 20))
+        );
+    }
+
+    #[test]
+    fn intersection_of_touching_or_disjoint_areas_is_none() {
+        assert_eq!(
+            area(0, 0, 100, 50).intersection(&area(100, 0, 10, 10)),
+            None
+        );
+        assert_eq!(area(0, 0, 100, 50).intersection(&area(0, 50, 10, 10)), None);
+        assert_eq!(
+            area(0, 0, 10, 10).intersection(&area(-50, -50, 20, 20)),
+            None
+        );
+    }
+
+    #[test]
+    fn iou_of_equal_half_and_disjoint_areas() {
+        assert_eq!(area(0, 0, 10, 10).iou(&area(0, 0, 10, 10)), 1.0);
+        assert_eq!(area(0, 0, 20, 10).iou(&area(10, 0, 20, 10)), 1.0 / 3.0);
+        assert_eq!(area(0, 0, 10, 10).iou(&area(20, 20, 10, 10)), 0.0);
+        assert_eq!(area(0, 0, 0, 0).iou(&area(0, 0, 0, 0)), 0.0);
+    }
+
+    #[test]
+    fn deflate_shrinks_each_side() {
+        assert_eq!(area(0, 0, 100, 50).deflate(10, 5), area(10, 5, 80, 40));
+        assert_eq!(area(0, 0, 100, 50).inflate(10, 5), area(-10, -5, 120, 60));
+    }
+
+    #[test]
+    fn deflate_beyond_the_size_collapses_to_the_center() {
+        let deflated = area(10, 20, 30, 10).deflate(40, 6);
+
+        assert_eq!(deflated, DisplayArea::from_points(25, 25, 25, 25));
+        assert!(deflated.is_empty());
+        assert_eq!(area(10, 20, 30, 10).deflate(5, 6), area(15, 25, 20, 0));
+    }
+
+    #[test]
+    fn split_grid_gives_the_remainder_to_the_last_row_and_column() {
+        let cells = area(100, 200, 10, 7).split_grid(2, 3);
+
+        assert_eq!(
+            cells,
+            vec![
+                area(100, 200, 3, 3),
+                area(103, 200, 3, 3),
+                area(106, 200, 4, 3),
+                area(100, 203, 3, 4),
+                area(103, 203, 3, 4),
+                area(106, 203, 4, 4),
+            ]
+        );
+        assert_eq!(
+            cells.iter().fold(cells[0], |all, cell| all.union(cell)),
+            area(100, 200, 10, 7)
+        );
+        assert!(area(0, 0, 10, 10).split_grid(0, 3).is_empty());
+    }
+
+    #[test]
+    fn clamp_to_moves_the_edges_inside_the_bounds() {
+        let screen = area(0, 0, 1920, 1080);
+
+        assert_eq!(
+            area(-50, 1000, 200, 200).clamp_to(&screen),
+            area(0, 1000, 150, 80)
+        );
+        assert_eq!(area(10, 10, 20, 20).clamp_to(&screen), area(10, 10, 20, 20));
+
+        let outside = area(2000, 100, 50, 50).clamp_to(&screen);
+        assert_eq!(outside, DisplayArea::from_points(1920, 100, 1920, 150));
+        assert!(outside.is_empty());
+    }
 }
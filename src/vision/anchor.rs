@@ -0,0 +1,242 @@
+use opencv::{
+    core::{Mat, Rect},
+    prelude::*,
+};
+
+use super::ocv::{MatchOptions, find_target_in_image};
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+/// Side of the anchor on which an [`AnchorRegion`] lies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Inside the anchor, offset from its top-left corner.
+    Inside,
+    /// Starts at the right edge of the anchor, top aligned.
+    Right,
+    /// Ends at the left edge of the anchor, top aligned.
+    Left,
+    /// Starts at the bottom edge of the anchor, left aligned.
+    Below,
+    /// Ends at the top edge of the anchor, left aligned.
+    Above,
+}
+
+/// Search region defined relative to a previously found anchor.
+///
+/// E.g. "the 200x40 area right of the `Login:` label" or "the 400x300 area below
+/// the dialog title". Searching only there avoids false positives elsewhere on the
+/// screen and is much faster than searching the whole screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnchorRegion {
+    pub direction: Direction,
+    /// Shift of the region; along the direction it moves the region away from the
+    /// anchor, across the direction it moves it right/down.
+    pub offset: (i32, i32),
+    /// Region size, `None` to use the size of the anchor.
+    pub size: Option<(u32, u32)>,
+}
+
+impl AnchorRegion {
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            offset: (0, 0),
+            size: None,
+        }
+    }
+
+    pub fn inside() -> Self {
+        Self::new(Direction::Inside)
+    }
+
+    pub fn right_of() -> Self {
+        Self::new(Direction::Right)
+    }
+
+    pub fn left_of() -> Self {
+        Self::new(Direction::Left)
+    }
+
+    pub fn below() -> Self {
+        Self::new(Direction::Below)
+    }
+
+    pub fn above() -> Self {
+        Self::new(Direction::Above)
+    }
+
+    pub fn offset(mut self, dx: i32, dy: i32) -> Self {
+        self.offset = (dx, dy);
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Computes the region for an anchor found at `anchor`.
+    ///
+    /// The result is in the same coordinates as `anchor`.
+    pub fn resolve(&self, anchor: &DisplayArea) -> DisplayArea {
+        let (_, (anchor_width, anchor_height)) = anchor.get_rectangle();
+        let (width, height) = self.size.unwrap_or((anchor_width, anchor_height));
+        let (dx, dy) = self.offset;
+
+        // Координаты относительно левого верхнего угла якоря
+        let (x, y) = match self.direction {
+            Direction::Inside => (dx, dy),
+            Direction::Right => (anchor_width as i32 + dx, dy),
+            Direction::Left => (-dx - width as i32, dy),
+            Direction::Below => (dx, anchor_height as i32 + dy),
+            Direction::Above => (dx, -dy - height as i32),
+        };
+
+        DisplayArea::from_rectangle(x, y, width, height).from_relative(anchor)
+    }
+}
+
+/// Finds `target` inside `region` of an anchor found at `anchor`.
+///
+/// # Arguments
+/// * `recognition` - Score threshold in `0.0..=1.0`
+/// * `source` - Image to search in
+/// * `anchor` - Anchor location in `source` coordinates
+/// * `region` - Where the target is expected relative to the anchor
+/// * `target` - Template to search for
+/// * `options` - Scale sweep and matching method
+///
+/// # Behavior
+/// - The region is clipped to `source`
+/// - The returned area is in `source` coordinates
+///
+/// # Errors
+/// Returns [`Error::InvalidArgument`] when the region lies outside of `source`,
+/// otherwise the errors of [`find_target_in_image`].
+pub fn find_target_near(
+    recognition: f32,
+    source: &Mat,
+    anchor: &DisplayArea,
+    region: &AnchorRegion,
+    target: &Mat,
+    options: &MatchOptions,
+) -> Result<DisplayArea> {
+    let bounds = DisplayArea::from_rectangle(0, 0, source.cols() as u32, source.rows() as u32);
+    let resolved = region.resolve(anchor);
+    let area = resolved.intersection(&bounds).ok_or_else(|| {
+        Error::InvalidArgument(format!(
+            "anchor region {:?} lies outside of the {}x{} source",
+            resolved,
+            source.cols(),
+            source.rows()
+        ))
+    })?;
+
+    let roi = Mat::roi(source, Rect::from(area))?;
+
+    Ok(find_target_in_image(recognition, &roi, target, options)?.from_relative(&area))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencv::core::{self, CV_8UC3, Scalar, Size};
+    use opencv::imgproc;
+
+    fn rectangle(area: DisplayArea) -> ((i32, i32), (u32, u32)) {
+        area.get_rectangle()
+    }
+
+    #[test]
+    fn resolve_places_the_region_on_every_side() {
+        let anchor = DisplayArea::from_rectangle(100, 50, 40, 20);
+
+        let cases = [
+            (AnchorRegion::inside(), ((100, 50), (40, 20))),
+            (
+                AnchorRegion::inside().offset(5, 3).size(10, 10),
+                ((105, 53), (10, 10)),
+            ),
+            (AnchorRegion::right_of(), ((140, 50), (40, 20))),
+            (
+                AnchorRegion::right_of().offset(10, 5).size(200, 40),
+                ((150, 55), (200, 40)),
+            ),
+            (AnchorRegion::left_of(), ((60, 50), (40, 20))),
+            (
+                AnchorRegion::left_of().offset(10, -2).size(60, 20),
+                ((30, 48), (60, 20)),
+            ),
+            (AnchorRegion::below(), ((100, 70), (40, 20))),
+            (
+                AnchorRegion::below().offset(-8, 4).size(80, 10),
+                ((92, 74), (80, 10)),
+            ),
+            (AnchorRegion::above(), ((100, 30), (40, 20))),
+            (
+                AnchorRegion::above().offset(2, 5).size(40, 30),
+                ((102, 15), (40, 30)),
+            ),
+        ];
+
+        for (region, expected) in cases {
+            assert_eq!(rectangle(region.resolve(&anchor)), expected, "{:?}", region);
+        }
+    }
+
+    /// Blurred noise: every patch of it is unique.
+    fn noise(width: i32, height: i32) -> Mat {
+        let mut noise =
+            Mat::new_rows_cols_with_default(height, width, CV_8UC3, Scalar::all(0.0)).unwrap();
+        core::randu(&mut noise, &Scalar::all(0.0), &Scalar::all(255.0)).unwrap();
+        let mut blurred = Mat::default();
+        imgproc::gaussian_blur_def(&noise, &mut blurred, Size::new(0, 0), 2.0).unwrap();
+        blurred
+    }
+
+    #[test]
+    fn find_target_near_searches_the_region_clipped_to_the_source() {
+        let source = noise(200, 150);
+        let target = Mat::roi(&source, Rect::new(182, 30, 16, 16))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+        let anchor = DisplayArea::from_rectangle(150, 20, 30, 20);
+        // Справа от якоря остаётся только полоса шириной 20 пикселей
+        let region = AnchorRegion::right_of().size(100, 40);
+
+        let found = find_target_near(
+            0.95,
+            &source,
+            &anchor,
+            &region,
+            &target,
+            &MatchOptions::single_scale(),
+        )
+        .unwrap();
+
+        assert_eq!(rectangle(found), ((182, 30), (16, 16)));
+    }
+
+    #[test]
+    fn find_target_near_rejects_a_region_outside_of_the_source() {
+        let source = noise(200, 150);
+        let target = Mat::roi(&source, Rect::new(10, 10, 16, 16))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+        let anchor = DisplayArea::from_rectangle(150, 20, 50, 20);
+
+        let result = find_target_near(
+            0.95,
+            &source,
+            &anchor,
+            &AnchorRegion::right_of(),
+            &target,
+            &MatchOptions::single_scale(),
+        );
+
+        assert!(matches!(result, Err(Error::InvalidArgument(_))));
+    }
+}
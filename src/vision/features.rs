@@ -0,0 +1,341 @@
+use opencv::{
+    calib3d::{RANSAC, find_homography},
+    core::{
+        DMatch, Mat, NORM_HAMMING, Point2f, Ptr, Vector, count_non_zero, extract_channel,
+        perspective_transform,
+    },
+    features2d::{AKAZE, BFMatcher, Feature2D, ORB},
+    prelude::*,
+};
+
+use super::ocv::to_grayscale;
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+/// Keypoint detector and descriptor used by [`find_target_by_features`].
+///
+/// Both produce binary descriptors that are compared with the Hamming norm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureDetector {
+    /// Fast, good for textured targets such as icons and logos.
+    Orb,
+    /// Slower, more stable under scaling and blur.
+    Akaze,
+}
+
+/// Parameters of the feature-based search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeatureOptions {
+    pub detector: FeatureDetector,
+    /// Lowe's ratio test: a match is kept when its distance is below `ratio` times
+    /// the distance of the second best candidate.
+    pub ratio: f32,
+    /// Minimum number of RANSAC inliers required to accept the homography.
+    pub min_inliers: usize,
+    /// Maximum reprojection error, in pixels, for a point to count as an inlier.
+    pub ransac_threshold: f64,
+}
+
+impl Default for FeatureOptions {
+    /// ORB, ratio 0.75, at least 10 inliers, 5 px reprojection threshold.
+    fn default() -> Self {
+        Self {
+            detector: FeatureDetector::Orb,
+            ratio: 0.75,
+            min_inliers: 10,
+            ransac_threshold: 5.0,
+        }
+    }
+}
+
+/// Location of a target found by [`find_target_by_features`].
+#[derive(Debug, Clone, Copy)]
+pub struct FeatureMatch {
+    /// Target corners projected into the source image: top-left, top-right,
+    /// bottom-right, bottom-left of the target.
+    pub corners: [(f32, f32); 4],
+    /// Bounding box of `corners` in source image coordinates.
+    pub area: DisplayArea,
+    /// Number of matches consistent with the homography.
+    pub inliers: usize,
+    /// Share of the ratio-test matches that are inliers, `0.0..=1.0`.
+    pub score: f64,
+}
+
+/// Finds `target` in `source` by keypoint matching and a RANSAC homography.
+///
+/// Unlike template matching this handles rotation, non-uniform scaling and
+/// perspective distortion, but needs textured targets: flat single-color
+/// elements produce no keypoints.
+///
+/// # Arguments
+/// * `source` - Image to search in
+/// * `target` - Image to search for; the alpha channel of a BGRA target limits
+///   keypoint detection to its opaque pixels
+/// * `options` - Detector and acceptance thresholds
+///
+/// # Errors
+/// - [`Error::EmptyImage`] for an empty `source` or `target`
+/// - [`Error::TemplateNotFound`] when there are too few inliers or the projected
+///   quadrilateral is degenerate; `best_score` is the inlier share
+pub fn find_target_by_features(
+    source: &Mat,
+    target: &Mat,
+    options: &FeatureOptions,
+) -> Result<FeatureMatch> {
+    if source.empty() {
+        return Err(Error::EmptyImage("source"));
+    }
+    if target.empty() {
+        return Err(Error::EmptyImage("target"));
+    }
+
+    let mut detector = create_detector(options.detector)?;
+
+    let mut target_mask = Mat::default();
+    if target.channels() == 4 {
+        extract_channel(target, &mut target_mask, 3)?;
+    }
+
+    let mut target_keypoints = Vector::new();
+    let mut target_descriptors = Mat::default();
+    detector.detect_and_compute(
+        &to_grayscale(target)?,
+        &target_mask,
+        &mut target_keypoints,
+        &mut target_descriptors,
+        false,
+    )?;
+
+    let mut source_keypoints = Vector::new();
+    let mut source_descriptors = Mat::default();
+    detector.detect_and_compute(
+        &to_grayscale(source)?,
+        &Mat::default(),
+        &mut source_keypoints,
+        &mut source_descriptors,
+        false,
+    )?;
+
+    if target_descriptors.empty() || source_descriptors.empty() {
+        return Err(Error::TemplateNotFound { best_score: 0.0 });
+    }
+
+    // Два ближайших соседа для ratio test
+    let matcher = BFMatcher::new(NORM_HAMMING, false)?;
+    let mut knn_matches = Vector::<Vector<DMatch>>::new();
+    matcher.knn_train_match(
+        &target_descriptors,
+        &source_descriptors,
+        &mut knn_matches,
+        2,
+        &Mat::default(),
+        false,
+    )?;
+
+    let mut target_points = Vector::<Point2f>::new();
+    let mut source_points = Vector::<Point2f>::new();
+    for pair in knn_matches.iter() {
+        if pair.len() < 2 {
+            continue;
+        }
+        let (best, second) = (pair.get(0)?, pair.get(1)?);
+        if best.distance < options.ratio * second.distance {
+            target_points.push(target_keypoints.get(best.query_idx as usize)?.pt());
+            source_points.push(source_keypoints.get(best.train_idx as usize)?.pt());
+        }
+    }
+
+    let good_matches = target_points.len();
+    // Для гомографии нужно минимум 4 точки
+    if good_matches < options.min_inliers.max(4) {
+        return Err(Error::TemplateNotFound { best_score: 0.0 });
+    }
+
+    let mut inlier_mask = Mat::default();
+    let homography = find_homography(
+        &target_points,
+        &source_points,
+        &mut inlier_mask,
+        RANSAC,
+        options.ransac_threshold,
+    )?;
+    if homography.empty() {
+        return Err(Error::TemplateNotFound { best_score: 0.0 });
+    }
+
+    let inliers = count_non_zero(&inlier_mask)? as usize;
+    let score = inliers as f64 / good_matches as f64;
+    if inliers < options.min_inliers {
+        return Err(Error::TemplateNotFound { best_score: score });
+    }
+
+    let (width, height) = (target.cols() as f32, target.rows() as f32);
+    let target_corners = Vector::<Point2f>::from_iter([
+        Point2f::new(0.0, 0.0),
+        Point2f::new(width, 0.0),
+        Point2f::new(width, height),
+        Point2f::new(0.0, height),
+    ]);
+    let mut projected = Vector::<Point2f>::new();
+    perspective_transform(&target_corners, &mut projected, &homography)?;
+
+    let mut corners = [(0.0, 0.0); 4];
+    for (corner, point) in corners.iter_mut().zip(projected.iter()) {
+        *corner = (point.x, point.y);
+    }
+    if !is_convex(&corners) {
+        return Err(Error::TemplateNotFound { best_score: score });
+    }
+
+    Ok(FeatureMatch {
+        corners,
+        area: bounding_area(&corners),
+        inliers,
+        score,
+    })
+}
+
+fn create_detector(detector: FeatureDetector) -> Result<Ptr<Feature2D>> {
+    Ok(match detector {
+        FeatureDetector::Orb => ORB::create_def()?.into(),
+        FeatureDetector::Akaze => AKAZE::create_def()?.into(),
+    })
+}
+
+/// Whether the quadrilateral keeps the orientation of the target rectangle,
+/// i.e. the homography did not fold or mirror it.
+fn is_convex(corners: &[(f32, f32); 4]) -> bool {
+    (0..4).all(|i| {
+        let (ax, ay) = corners[i];
+        let (bx, by) = corners[(i + 1) % 4];
+        let (cx, cy) = corners[(i + 2) % 4];
+        (bx - ax) * (cy - by) - (by - ay) * (cx - bx) > 0.0
+    })
+}
+
+fn bounding_area(corners: &[(f32, f32); 4]) -> DisplayArea {
+    let min_x = corners.iter().map(|c| c.0).fold(f32::INFINITY, f32::min);
+    let min_y = corners.iter().map(|c| c.1).fold(f32::INFINITY, f32::min);
+    let max_x = corners
+        .iter()
+        .map(|c| c.0)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let max_y = corners
+        .iter()
+        .map(|c| c.1)
+        .fold(f32::NEG_INFINITY, f32::max);
+
+    DisplayArea::from_points(
+        min_x.floor() as i32,
+        min_y.floor() as i32,
+        max_x.ceil() as i32,
+        max_y.ceil() as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencv::core::{BORDER_CONSTANT, CV_8UC3, Rect, Scalar};
+    use opencv::imgproc;
+
+    /// 400x400 image of overlapping random rectangles: plenty of distinct corners.
+    fn texture(seed: u64) -> Mat {
+        let mut image =
+            Mat::new_rows_cols_with_default(400, 400, CV_8UC3, Scalar::all(128.0)).unwrap();
+        let mut state = seed;
+        let mut next = |max: i32| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) % max as u64) as i32
+        };
+        for _ in 0..150 {
+            let (x, y) = (next(380), next(380));
+            let (w, h) = (8 + next(40), 8 + next(40));
+            let color = Scalar::new(next(256) as f64, next(256) as f64, next(256) as f64, 0.0);
+            imgproc::rectangle(
+                &mut image,
+                Rect::new(x, y, w, h),
+                color,
+                imgproc::FILLED,
+                imgproc::LINE_8,
+                0,
+            )
+            .unwrap();
+        }
+        image
+    }
+
+    /// Rotates `image` by `angle` degrees and scales it by `scale` around its center.
+    fn rotate(image: &Mat, angle: f64, scale: f64) -> Mat {
+        let center = Point2f::new(image.cols() as f32 / 2.0, image.rows() as f32 / 2.0);
+        let matrix = imgproc::get_rotation_matrix_2d(center, angle, scale).unwrap();
+        let mut rotated = Mat::default();
+        imgproc::warp_affine(
+            image,
+            &mut rotated,
+            &matrix,
+            image.size().unwrap(),
+            imgproc::INTER_LINEAR,
+            BORDER_CONSTANT,
+            Scalar::all(128.0),
+        )
+        .unwrap();
+        rotated
+    }
+
+    #[test]
+    fn finds_a_rotated_and_scaled_crop() {
+        let image = texture(1);
+        let target = Mat::roi(&image, Rect::new(120, 120, 160, 160))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+        // Центр вырезки совпадает с центром поворота
+        let source = rotate(&image, 25.0, 0.8);
+
+        let found = find_target_by_features(&source, &target, &FeatureOptions::default()).unwrap();
+
+        let (x, y) = found.area.get_average_point();
+        assert!(
+            (x - 200).abs() <= 4 && (y - 200).abs() <= 4,
+            "{:?}",
+            found.area
+        );
+        // Повёрнутый на 25° квадрат 128x128 занимает около 170x170
+        let (_, (width, height)) = found.area.get_rectangle();
+        assert!((160..=180).contains(&width), "{:?}", found.area);
+        assert!((160..=180).contains(&height), "{:?}", found.area);
+        assert!(found.inliers >= 10);
+    }
+
+    #[test]
+    fn unrelated_image_is_not_found() {
+        let target = Mat::roi(&texture(1), Rect::new(120, 120, 160, 160))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+        let source = texture(2);
+
+        assert!(matches!(
+            find_target_by_features(&source, &target, &FeatureOptions::default()),
+            Err(Error::TemplateNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn empty_images_are_rejected() {
+        let image = texture(1);
+
+        assert!(matches!(
+            find_target_by_features(&Mat::default(), &image, &FeatureOptions::default()),
+            Err(Error::EmptyImage("source"))
+        ));
+        assert!(matches!(
+            find_target_by_features(&image, &Mat::default(), &FeatureOptions::default()),
+            Err(Error::EmptyImage("target"))
+        ));
+    }
+}
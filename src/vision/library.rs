@@ -0,0 +1,511 @@
+use opencv::{
+    core::{self, Mat, Vector},
+    imgcodecs, imgproc,
+    prelude::*,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::ocv::{MatchMode, MatchOptions, load_template};
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+/// Manifest file names looked up by [`TemplateLibrary::open`], in this order.
+pub const MANIFEST_NAMES: [&str; 2] = ["templates.toml", "templates.json"];
+
+/// Recognition threshold of templates that do not set one.
+pub const DEFAULT_THRESHOLD: f32 = 0.8;
+
+/// Named templates loaded from a directory and its manifest.
+///
+/// The manifest lists every template with its matching settings, so code refers
+/// to targets by name instead of passing images and thresholds around:
+///
+/// ```toml
+/// [[templates]]
+/// name = "login_button"
+/// file = "login_button.png"
+/// threshold = 0.9
+/// scale = [0.9, 1.1]
+/// scale_step = 0.05
+/// region = [0, 600, 1920, 480]   # x, y, width, height in screen coordinates
+/// mode = "grayscale"             # color | grayscale | edges | gradient
+///
+/// [[templates]]
+/// name = "close"
+/// file = "close.png"
+/// mask = "close_mask.png"        # true (alpha channel, default) | false | mask file
+/// ```
+///
+/// The JSON manifest has the same shape: `{ "templates": [ { "name": ..., ... } ] }`.
+/// Images are decoded on first use and cached for the lifetime of the library.
+pub struct TemplateLibrary {
+    templates: HashMap<String, NamedTemplate>,
+}
+
+/// A template of a [`TemplateLibrary`] with its matching settings.
+pub struct NamedTemplate {
+    pub name: String,
+    /// Recognition threshold in `0.0..=1.0`.
+    pub threshold: f32,
+    pub options: MatchOptions,
+    /// Area of the screen the template is expected in, if known.
+    pub region: Option<DisplayArea>,
+    file: PathBuf,
+    mask: MaskSetting,
+    image: OnceLock<Mat>,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    templates: Vec<ManifestEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestEntry {
+    name: String,
+    file: PathBuf,
+    threshold: Option<f32>,
+    #[serde(default)]
+    mask: MaskSetting,
+    scale: Option<(f64, f64)>,
+    scale_step: Option<f64>,
+    region: Option<(i32, i32, u32, u32)>,
+    #[serde(default)]
+    mode: ManifestMode,
+    /// Canny thresholds for `mode = "edges"`.
+    canny: Option<(f64, f64)>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MaskSetting {
+    /// Use (`true`) or drop (`false`) the alpha channel of the image.
+    Alpha(bool),
+    /// Separate single-channel mask image, non-zero pixels are matched.
+    File(PathBuf),
+}
+
+impl Default for MaskSetting {
+    fn default() -> Self {
+        MaskSetting::Alpha(true)
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum ManifestMode {
+    #[default]
+    Color,
+    Grayscale,
+    Edges,
+    Gradient,
+}
+
+impl TemplateLibrary {
+    /// Loads the manifest found in `dir` (see [`MANIFEST_NAMES`]).
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let manifest = MANIFEST_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                Error::InvalidManifest(format!(
+                    "no {} in {}",
+                    MANIFEST_NAMES.join(" or "),
+                    dir.display()
+                ))
+            })?;
+        Self::from_manifest(manifest)
+    }
+
+    /// Loads a TOML or JSON manifest, chosen by the file extension.
+    ///
+    /// Template files are resolved relative to the manifest directory.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidManifest`] for a malformed manifest, duplicate names,
+    /// invalid thresholds or scale ranges. Images are not checked until first use,
+    /// see [`TemplateLibrary::preload`].
+    pub fn from_manifest(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let manifest: Manifest = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&text)
+                .map_err(|e| Error::InvalidManifest(format!("{}: {}", path.display(), e)))?,
+            Some("json") => serde_json::from_str(&text)
+                .map_err(|e| Error::InvalidManifest(format!("{}: {}", path.display(), e)))?,
+            _ => {
+                return Err(Error::InvalidManifest(format!(
+                    "{}: expected a .toml or .json file",
+                    path.display()
+                )));
+            }
+        };
+
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let mut templates = HashMap::with_capacity(manifest.templates.len());
+        for entry in manifest.templates {
+            let template = NamedTemplate::from_entry(entry, dir)?;
+            if templates.contains_key(&template.name) {
+                return Err(Error::InvalidManifest(format!(
+                    "duplicate template \"{}\"",
+                    template.name
+                )));
+            }
+            templates.insert(template.name.clone(), template);
+        }
+
+        Ok(Self { templates })
+    }
+
+    pub fn get(&self, name: &str) -> Result<&NamedTemplate> {
+        self.templates
+            .get(name)
+            .ok_or_else(|| Error::UnknownTemplate(name.to_string()))
+    }
+
+    /// Decoded image of the template `name`.
+    pub fn image(&self, name: &str) -> Result<&Mat> {
+        self.get(name)?.image()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// Decodes every image now, so missing or broken files are reported at startup
+    /// rather than in the middle of a run.
+    pub fn preload(&self) -> Result<()> {
+        for template in self.templates.values() {
+            template.image()?;
+        }
+        Ok(())
+    }
+}
+
+impl NamedTemplate {
+    fn from_entry(entry: ManifestEntry, dir: &Path) -> Result<Self> {
+        let threshold = entry.threshold.unwrap_or(DEFAULT_THRESHOLD);
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(Error::InvalidManifest(format!(
+                "template \"{}\": threshold must be between 0 and 1, got {}",
+                entry.name, threshold
+            )));
+        }
+
+        let mut options = MatchOptions::default();
+        if let Some((min, max)) = entry.scale {
+            options.scale_min = min;
+            options.scale_max = max;
+        }
+        if let Some(step) = entry.scale_step {
+            options.scale_step = step;
+        }
+        let valid_scales = options.scale_min > 0.0
+            && options.scale_min <= options.scale_max
+            && (options.scale_min == options.scale_max || options.scale_step > 0.0);
+        if !valid_scales {
+            return Err(Error::InvalidManifest(format!(
+                "template \"{}\": invalid scale range {}..={} step {}",
+                entry.name, options.scale_min, options.scale_max, options.scale_step
+            )));
+        }
+
+        let (low, high) = entry.canny.unwrap_or((50.0, 150.0));
+        options.mode = match entry.mode {
+            ManifestMode::Color => MatchMode::Color,
+            ManifestMode::Grayscale => MatchMode::Grayscale,
+            ManifestMode::Edges => MatchMode::Edges { low, high },
+            ManifestMode::Gradient => MatchMode::Gradient,
+        };
+
+        let mask = match entry.mask {
+            MaskSetting::File(file) => MaskSetting::File(dir.join(file)),
+            alpha => alpha,
+        };
+
+        Ok(Self {
+            name: entry.name,
+            threshold,
+            options,
+            region: entry
+                .region
+                .map(|(x, y, w, h)| DisplayArea::from_rectangle(x, y, w, h)),
+            file: dir.join(entry.file),
+            mask,
+            image: OnceLock::new(),
+        })
+    }
+
+    /// Decoded template image, BGRA when it carries a mask, BGR otherwise.
+    pub fn image(&self) -> Result<&Mat> {
+        if let Some(image) = self.image.get() {
+            return Ok(image);
+        }
+        let image = self.load()?;
+        Ok(self.image.get_or_init(|| image))
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    fn load(&self) -> Result<Mat> {
+        let image = load_template(&self.file)?;
+
+        match &self.mask {
+            MaskSetting::Alpha(true) => Ok(image),
+            MaskSetting::Alpha(false) => drop_alpha(image),
+            MaskSetting::File(path) => {
+                let path_str = path.to_str().ok_or_else(|| {
+                    Error::InvalidManifest(format!(
+                        "mask path is not valid UTF-8: {}",
+                        path.display()
+                    ))
+                })?;
+                let mask = imgcodecs::imread(path_str, imgcodecs::IMREAD_GRAYSCALE)?;
+                if mask.empty() {
+                    return Err(Error::InvalidManifest(format!(
+                        "can not decode mask {}",
+                        path.display()
+                    )));
+                }
+                if mask.size()? != image.size()? {
+                    return Err(Error::InvalidManifest(format!(
+                        "template \"{}\": mask {} does not match the image size",
+                        self.name,
+                        path.display()
+                    )));
+                }
+
+                let mut channels = Vector::<Mat>::new();
+                core::split(&drop_alpha(image)?, &mut channels)?;
+                channels.push(mask);
+                let mut bgra = Mat::default();
+                core::merge(&channels, &mut bgra)?;
+                Ok(bgra)
+            }
+        }
+    }
+}
+
+fn drop_alpha(image: Mat) -> Result<Mat> {
+    if image.channels() != 4 {
+        return Ok(image);
+    }
+    let mut bgr = Mat::default();
+    imgproc::cvt_color(
+        &image,
+        &mut bgr,
+        imgproc::COLOR_BGRA2BGR,
+        0,
+        core::AlgorithmHint::ALGO_HINT_DEFAULT,
+    )?;
+    Ok(bgr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencv::core::{CV_8UC1, CV_8UC3, Scalar};
+
+    /// Temporary directory with a 20x10 `icon.png`, an 8x8 `small_mask.png` and
+    /// the given manifests.
+    fn library_dir(name: &str, manifests: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mavis_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let icon = Mat::new_rows_cols_with_default(10, 20, CV_8UC3, Scalar::all(90.0)).unwrap();
+        imgcodecs::imwrite(
+            dir.join("icon.png").to_str().unwrap(),
+            &icon,
+            &Vector::new(),
+        )
+        .unwrap();
+        let mask = Mat::new_rows_cols_with_default(8, 8, CV_8UC1, Scalar::all(255.0)).unwrap();
+        imgcodecs::imwrite(
+            dir.join("small_mask.png").to_str().unwrap(),
+            &mask,
+            &Vector::new(),
+        )
+        .unwrap();
+        for (file, text) in manifests {
+            std::fs::write(dir.join(file), text).unwrap();
+        }
+        dir
+    }
+
+    fn invalid_manifest(name: &str, toml: &str) -> bool {
+        let dir = library_dir(name, &[("templates.toml", toml)]);
+        let result = TemplateLibrary::from_manifest(dir.join("templates.toml"));
+        std::fs::remove_dir_all(dir).unwrap();
+        matches!(result, Err(Error::InvalidManifest(_)))
+    }
+
+    #[test]
+    fn toml_manifest_sets_the_matching_options() {
+        let dir = library_dir(
+            "library_toml",
+            &[(
+                "templates.toml",
+                r#"
+                [[templates]]
+                name = "login"
+                file = "icon.png"
+                threshold = 0.9
+                scale = [0.9, 1.1]
+                scale_step = 0.05
+                region = [0, 600, 1920, 480]
+                mode = "edges"
+                canny = [20.0, 60.0]
+
+                [[templates]]
+                name = "close"
+                file = "icon.png"
+                "#,
+            )],
+        );
+
+        let library = TemplateLibrary::open(&dir).unwrap();
+
+        assert_eq!(library.len(), 2);
+        let login = library.get("login").unwrap();
+        assert_eq!(login.threshold, 0.9);
+        assert_eq!(
+            (login.options.scale_min, login.options.scale_max),
+            (0.9, 1.1)
+        );
+        assert_eq!(login.options.scale_step, 0.05);
+        assert_eq!(
+            login.options.mode,
+            MatchMode::Edges {
+                low: 20.0,
+                high: 60.0
+            }
+        );
+        assert_eq!(
+            login.region.map(|area| area.get_rectangle()),
+            Some(((0, 600), (1920, 480)))
+        );
+        assert_eq!(login.file(), dir.join("icon.png"));
+
+        let close = library.get("close").unwrap();
+        assert_eq!(close.threshold, DEFAULT_THRESHOLD);
+        assert_eq!(close.options, MatchOptions::default());
+        assert!(close.region.is_none());
+
+        assert!(matches!(
+            library.get("missing"),
+            Err(Error::UnknownTemplate(_))
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn json_manifest_has_the_same_shape() {
+        let dir = library_dir(
+            "library_json",
+            &[(
+                "templates.json",
+                r#"{ "templates": [
+                    { "name": "ok", "file": "icon.png", "threshold": 0.7,
+                      "mode": "grayscale", "mask": false }
+                ] }"#,
+            )],
+        );
+
+        let library = TemplateLibrary::open(&dir).unwrap();
+
+        let ok = library.get("ok").unwrap();
+        assert_eq!(ok.threshold, 0.7);
+        assert_eq!(ok.options.mode, MatchMode::Grayscale);
+        assert_eq!(ok.image().unwrap().channels(), 3);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        assert!(invalid_manifest(
+            "library_duplicate",
+            r#"
+            [[templates]]
+            name = "ok"
+            file = "icon.png"
+
+            [[templates]]
+            name = "ok"
+            file = "icon.png"
+            "#,
+        ));
+    }
+
+    #[test]
+    fn threshold_out_of_range_is_rejected() {
+        assert!(invalid_manifest(
+            "library_threshold",
+            r#"
+            [[templates]]
+            name = "ok"
+            file = "icon.png"
+            threshold = 1.5
+            "#,
+        ));
+    }
+
+    #[test]
+    fn invalid_scale_ranges_are_rejected() {
+        assert!(invalid_manifest(
+            "library_scale_order",
+            r#"
+            [[templates]]
+            name = "ok"
+            file = "icon.png"
+            scale = [1.2, 0.8]
+            "#,
+        ));
+        assert!(invalid_manifest(
+            "library_scale_step",
+            r#"
+            [[templates]]
+            name = "ok"
+            file = "icon.png"
+            scale = [0.8, 1.2]
+            scale_step = 0.0
+            "#,
+        ));
+    }
+
+    #[test]
+    fn mask_of_another_size_is_rejected_on_load() {
+        let dir = library_dir(
+            "library_mask",
+            &[(
+                "templates.toml",
+                r#"
+                [[templates]]
+                name = "ok"
+                file = "icon.png"
+                mask = "small_mask.png"
+                "#,
+            )],
+        );
+
+        // Изображения проверяются только при первом использовании
+        let library = TemplateLibrary::from_manifest(dir.join("templates.toml")).unwrap();
+
+        assert!(matches!(library.preload(), Err(Error::InvalidManifest(_))));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,2 +1,8 @@
+pub mod anchor;
+pub mod features;
+pub mod library;
+pub mod ocr_result;
 pub mod ocv;
+pub mod preprocess;
+pub mod text;
 pub mod tsrt;
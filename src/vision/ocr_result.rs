@@ -0,0 +1,322 @@
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+/// A recognized word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrWord {
+    pub text: String,
+    pub area: DisplayArea,
+    /// Tesseract confidence in `0.0..=100.0`.
+    pub confidence: f32,
+}
+
+/// A line of text: words on one baseline, left to right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrLine {
+    pub area: DisplayArea,
+    pub words: Vec<OcrWord>,
+}
+
+impl OcrLine {
+    /// Words joined with single spaces.
+    pub fn text(&self) -> String {
+        self.words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Mean confidence of the words.
+    pub fn confidence(&self) -> f32 {
+        mean_confidence(self.words.iter())
+    }
+}
+
+/// A block of text: a column, a paragraph or a caption, top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrBlock {
+    pub area: DisplayArea,
+    pub lines: Vec<OcrLine>,
+}
+
+impl OcrBlock {
+    /// Lines joined with newlines.
+    pub fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(OcrLine::text)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Mean confidence of the words.
+    pub fn confidence(&self) -> f32 {
+        mean_confidence(self.lines.iter().flat_map(|line| &line.words))
+    }
+}
+
+/// Text of an image with the position and confidence of every block, line and word.
+///
+/// Areas are in the coordinates of the recognized image; use [`OcrResult::offset_by`]
+/// to move them to the screen.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OcrResult {
+    pub blocks: Vec<OcrBlock>,
+}
+
+impl OcrResult {
+    /// Parses the TSV output of tesseract (`tesseract image stdout tsv`).
+    ///
+    /// Paragraphs are folded into their blocks; words without text and lines or
+    /// blocks without words are dropped.
+    ///
+    /// # Errors
+    /// Returns [`Error::OcrFailed`] for a malformed row.
+    pub fn from_tsv(tsv: &str) -> Result<Self> {
+        let mut result = OcrResult::default();
+
+        for (index, row) in tsv.lines().enumerate() {
+            if row.is_empty() || row.starts_with("level") {
+                continue;
+            }
+            let row = TsvRow::parse(row).ok_or_else(|| {
+                Error::OcrFailed(format!("malformed tsv row {}: {}", index + 1, row))
+            })?;
+
+            match row.level {
+                BLOCK_LEVEL => result.blocks.push(OcrBlock {
+                    area: row.area,
+                    lines: Vec::new(),
+                }),
+                LINE_LEVEL => {
+                    if let Some(block) = result.blocks.last_mut() {
+                        block.lines.push(OcrLine {
+                            area: row.area,
+                            words: Vec::new(),
+                        });
+                    }
+                }
+                WORD_LEVEL if !row.text.trim().is_empty() => {
+                    if let Some(line) = result
+                        .blocks
+                        .last_mut()
+                        .and_then(|block| block.lines.last_mut())
+                    {
+                        line.words.push(OcrWord {
+                            text: row.text.trim().to_string(),
+                            area: row.area,
+                            confidence: row.confidence,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for block in &mut result.blocks {
+            block.lines.retain(|line| !line.words.is_empty());
+        }
+        result.blocks.retain(|block| !block.lines.is_empty());
+        Ok(result)
+    }
+
+    /// Whole text: lines joined with newlines, blocks separated by an empty line.
+    pub fn text(&self) -> String {
+        self.blocks
+            .iter()
+            .map(OcrBlock::text)
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &OcrLine> {
+        self.blocks.iter().flat_map(|block| &block.lines)
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &OcrWord> {
+        self.lines().flat_map(|line| &line.words)
+    }
+
+    /// Words whose center lies inside `area`.
+    pub fn words_in<'a>(&'a self, area: &'a DisplayArea) -> impl Iterator<Item = &'a OcrWord> {
+        self.words()
+            .filter(move |word| area.contains_point(word.area.get_average_point()))
+    }
+
+    /// Text of the words inside `area`, joined with single spaces.
+    pub fn text_in(&self, area: &DisplayArea) -> String {
+        self.words_in(area)
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Mean confidence of all words, 0.0 if there are none.
+    pub fn confidence(&self) -> f32 {
+        mean_confidence(self.words())
+    }
+
+    /// Moves every area from the coordinates of `area` to the ones `area` is given in,
+    /// see [`DisplayArea::from_relative`].
+    pub fn offset_by(self, area: &DisplayArea) -> Self {
+        self.map_areas(|word_area| word_area.from_relative(area))
+    }
+
+    /// Replaces every block, line and word area with `map(area)`.
+    pub fn map_areas(mut self, map: impl Fn(&DisplayArea) -> DisplayArea) -> Self {
+        for block in &mut self.blocks {
+            block.area = map(&block.area);
+            for line in &mut block.lines {
+                line.area = map(&line.area);
+                for word in &mut line.words {
+                    word.area = map(&word.area);
+                }
+            }
+        }
+        self
+    }
+}
+
+const BLOCK_LEVEL: u8 = 2;
+const LINE_LEVEL: u8 = 4;
+const WORD_LEVEL: u8 = 5;
+
+/// `level page_num block_num par_num line_num word_num left top width height conf text`
+struct TsvRow<'a> {
+    level: u8,
+    area: DisplayArea,
+    confidence: f32,
+    text: &'a str,
+}
+
+impl<'a> TsvRow<'a> {
+    fn parse(row: &'a str) -> Option<Self> {
+        let mut columns = row.splitn(12, '\t');
+        let level = columns.next()?.parse().ok()?;
+        let mut columns = columns.skip(5);
+        let left = columns.next()?.parse().ok()?;
+        let top = columns.next()?.parse().ok()?;
+        let width = columns.next()?.parse().ok()?;
+        let height = columns.next()?.parse().ok()?;
+        let confidence = columns.next()?.parse().ok()?;
+        let text = columns.next().unwrap_or("");
+
+        Some(Self {
+            level,
+            area: DisplayArea::from_rectangle(left, top, width, height),
+            confidence,
+            text,
+        })
+    }
+}
+
+fn mean_confidence<'a>(words: impl Iterator<Item = &'a OcrWord>) -> f32 {
+    let (sum, count) = words.fold((0.0, 0), |(sum, count), word| {
+        (sum + word.confidence, count + 1)
+    });
+    if count == 0 { 0.0 } else { sum / count as f32 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `tesseract screenshot.png stdout tsv` of a toolbar and a status line.
+    const TSV: &str = "\
+level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t800\t600\t-1\t
+2\t1\t1\t0\t0\t0\t36\t92\t330\t30\t-1\t
+3\t1\t1\t1\t0\t0\t36\t92\t330\t30\t-1\t
+4\t1\t1\t1\t1\t0\t36\t92\t330\t30\t-1\t
+5\t1\t1\t1\t1\t1\t36\t92\t90\t30\t96.06375\tFile
+5\t1\t1\t1\t1\t2\t140\t92\t70\t30\t95.5\tSave
+5\t1\t1\t1\t1\t3\t220\t94\t30\t28\t91.25\tas
+5\t1\t1\t1\t1\t4\t266\t92\t100\t30\t93\tClose
+2\t1\t2\t0\t0\t0\t600\t20\t40\t40\t-1\t
+3\t1\t2\t1\t0\t0\t600\t20\t40\t40\t-1\t
+4\t1\t2\t1\t1\t0\t600\t20\t40\t40\t-1\t
+5\t1\t2\t1\t1\t1\t600\t20\t40\t40\t-1\t
+2\t1\t3\t0\t0\t0\t36\t540\t180\t24\t-1\t
+3\t1\t3\t1\t0\t0\t36\t540\t180\t24\t-1\t
+4\t1\t3\t1\t1\t0\t36\t540\t180\t24\t-1\t
+5\t1\t3\t1\t1\t1\t36\t540\t70\t24\t88.5\tTotal:
+5\t1\t3\t1\t1\t2\t112\t540\t30\t24\t90.5\t42
+5\t1\t3\t1\t1\t3\t148\t540\t68\t24\t95\t
+5\t1\t3\t1\t1\t4\t222\t540\t60\t24\t92\titems
+";
+
+    #[test]
+    fn from_tsv_builds_the_layout() {
+        let result = OcrResult::from_tsv(TSV).unwrap();
+
+        // Блок 2 содержит только пустое слово (иконку) и отбрасывается
+        assert_eq!(result.blocks.len(), 2);
+        assert_eq!(result.text(), "File Save as Close\n\nTotal: 42 items");
+        assert_eq!(
+            result.blocks[0].area,
+            DisplayArea::from_rectangle(36, 92, 330, 30)
+        );
+        assert_eq!(
+            result.words().next().unwrap(),
+            &OcrWord {
+                text: "File".to_string(),
+                area: DisplayArea::from_rectangle(36, 92, 90, 30),
+                confidence: 96.06375,
+            }
+        );
+        assert_eq!(result.words().count(), 7);
+        // Пустое слово не влияет на уверенность строки
+        assert!((result.lines().nth(1).unwrap().confidence() - 90.333).abs() < 0.001);
+    }
+
+    #[test]
+    fn from_tsv_accepts_empty_output() {
+        let header = TSV.lines().next().unwrap();
+
+        assert!(OcrResult::from_tsv("").unwrap().blocks.is_empty());
+        assert!(OcrResult::from_tsv(header).unwrap().blocks.is_empty());
+        assert_eq!(OcrResult::from_tsv("").unwrap().confidence(), 0.0);
+    }
+
+    #[test]
+    fn from_tsv_rejects_a_malformed_row() {
+        let result = OcrResult::from_tsv("5\t1\t1\t1\t1\t1\t36\t92\tninety\t30\t96\tFile");
+
+        assert!(matches!(result, Err(Error::OcrFailed(_))));
+    }
+
+    #[test]
+    fn text_in_selects_words_by_their_center() {
+        let result = OcrResult::from_tsv(TSV).unwrap();
+
+        assert_eq!(
+            result.text_in(&DisplayArea::from_rectangle(100, 80, 200, 50)),
+            "Save as"
+        );
+        assert_eq!(
+            result.text_in(&DisplayArea::from_rectangle(0, 0, 10, 10)),
+            ""
+        );
+    }
+
+    #[test]
+    fn offset_by_moves_every_area() {
+        let result = OcrResult::from_tsv(TSV)
+            .unwrap()
+            .offset_by(&DisplayArea::from_rectangle(1000, 200, 800, 600));
+
+        assert_eq!(
+            result.blocks[1].area,
+            DisplayArea::from_rectangle(1036, 740, 180, 24)
+        );
+        assert_eq!(
+            result.blocks[1].lines[0].area,
+            DisplayArea::from_rectangle(1036, 740, 180, 24)
+        );
+        assert_eq!(
+            result.words().last().unwrap().area,
+            DisplayArea::from_rectangle(1222, 740, 60, 24)
+        );
+    }
+}
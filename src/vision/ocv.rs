@@ -1,78 +1,458 @@
 use opencv::{
-    core::{Mat, Point, Size, min_max_loc},
-    imgproc::{INTER_LINEAR, TM_CCOEFF_NORMED, match_template, resize},
+    core::{
+        AlgorithmHint, BORDER_CONSTANT, BORDER_DEFAULT, CV_8U, CV_32F, Mat, NORM_MINMAX, Point,
+        Rect, Size, extract_channel, magnitude, min_max_loc, normalize, patch_na_ns,
+    },
+    imgcodecs::{IMREAD_UNCHANGED, imread},
+    imgproc::{
+        COLOR_BGR2GRAY, COLOR_BGRA2BGR, COLOR_BGRA2GRAY, COLOR_GRAY2BGR, INTER_AREA,
+        INTER_LINEAR, INTER_NEAREST, TM_CCOEFF_NORMED, TM_CCORR_NORMED, TM_SQDIFF_NORMED, canny,
+        cvt_color, dilate, match_template, morphology_default_border_value, resize, sobel,
+    },
     prelude::*,
 };
+use crate::error::{Error, Result};
 use crate::utils::DisplayArea;
+use std::borrow::Cow;
+use std::path::Path;
 
 
 
 
-pub fn find_target_in_image(
-    recognition: f32,
-    source: &Mat,
-    target: &Mat,
-) -> Result<DisplayArea, Box<dyn std::error::Error>> {
+/// Template matching method used to produce a [`Match`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MatchMethod {
+    /// `TM_CCOEFF_NORMED`: normalized correlation coefficient, 1.0 is a perfect match.
+    CcoeffNormed,
+    /// `TM_SQDIFF_NORMED`: normalized squared difference, reported as `1.0 - value`
+    /// so that higher is still better.
+    SqdiffNormed,
+    /// `TM_CCORR_NORMED`: normalized cross correlation, 1.0 is a perfect match.
+    CcorrNormed,
+}
+
+impl MatchMethod {
+    fn to_opencv(self) -> i32 {
+        match self {
+            MatchMethod::CcoeffNormed => TM_CCOEFF_NORMED,
+            MatchMethod::SqdiffNormed => TM_SQDIFF_NORMED,
+            MatchMethod::CcorrNormed => TM_CCORR_NORMED,
+        }
+    }
+}
+
+/// Preprocessing applied to both the source and the template before matching.
+///
+/// Everything except [`MatchMode::Color`] works on luminance, so a template captured
+/// with one color theme can still be found with another; `Edges` and `Gradient` also
+/// ignore the polarity of the contrast (dark text on light and vice versa).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchMode {
+    /// Plain BGR matching.
+    Color,
+    /// Grayscale matching.
+    Grayscale,
+    /// Matching of Canny edge maps with the given hysteresis thresholds.
+    Edges { low: f64, high: f64 },
+    /// Matching of Sobel gradient magnitudes normalized to `0..=255`.
+    Gradient,
+}
+
+impl MatchMode {
+    /// Converts `image` into the representation used by this mode.
+    fn apply<'a>(self, image: &'a Mat) -> Result<Cow<'a, Mat>> {
+        if self == MatchMode::Color {
+            return Ok(Cow::Borrowed(image));
+        }
+
+        let gray = to_grayscale(image)?;
+        match self {
+            MatchMode::Color | MatchMode::Grayscale => Ok(Cow::Owned(gray)),
+            MatchMode::Edges { low, high } => {
+                let mut edges = Mat::default();
+                canny(&gray, &mut edges, low, high, 3, false)?;
+                Ok(Cow::Owned(edges))
+            }
+            MatchMode::Gradient => {
+                let mut grad_x = Mat::default();
+                let mut grad_y = Mat::default();
+                sobel(&gray, &mut grad_x, CV_32F, 1, 0, 3, 1.0, 0.0, BORDER_DEFAULT)?;
+                sobel(&gray, &mut grad_y, CV_32F, 0, 1, 3, 1.0, 0.0, BORDER_DEFAULT)?;
 
+                let mut magnitude_map = Mat::default();
+                magnitude(&grad_x, &grad_y, &mut magnitude_map)?;
+
+                // Нормализация, чтобы контраст темы не влиял на величину градиента
+                let mut normalized = Mat::default();
+                normalize(
+                    &magnitude_map,
+                    &mut normalized,
+                    0.0,
+                    255.0,
+                    NORM_MINMAX,
+                    CV_8U,
+                    &Mat::default(),
+                )?;
+                Ok(Cow::Owned(normalized))
+            }
+        }
+    }
+}
+
+pub(crate) fn to_grayscale(image: &Mat) -> Result<Mat> {
+    if image.channels() == 1 {
+        return Ok(image.try_clone()?);
+    }
+
+    let code = if image.channels() == 4 {
+        COLOR_BGRA2GRAY
+    } else {
+        COLOR_BGR2GRAY
+    };
+    let mut gray = Mat::default();
+    cvt_color(image, &mut gray, code, 0, AlgorithmHint::ALGO_HINT_DEFAULT)?;
+    Ok(gray)
+}
+
+/// How [`find_best_match`] covers the source image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SearchStrategy {
+    /// Every scale is matched over the whole source at full resolution.
+    Exhaustive,
+    /// The scale sweep runs on source and template downsampled `levels` times by 2;
+    /// the best candidate is then refined at full resolution in a small ROI around
+    /// it, over the neighbouring scales only.
+    ///
+    /// Much faster on large screens, but may miss small or low-contrast targets
+    /// that do not survive downsampling.
+    Pyramid { levels: u32 },
+}
+
+/// Parameters of the multi-scale template search.
+///
+/// The template is resized to every scale from `scale_min` to `scale_max`
+/// (inclusive) in `scale_step` increments and matched against the source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchOptions {
+    pub scale_min: f64,
+    pub scale_max: f64,
+    pub scale_step: f64,
+    /// OpenCV interpolation flag used to resize the template, e.g. `INTER_LINEAR`.
+    pub interpolation: i32,
+    pub method: MatchMethod,
+    pub mode: MatchMode,
+    /// Only used by [`find_best_match`] and the functions built on it;
+    /// [`find_all_targets`] is always exhaustive.
+    pub strategy: SearchStrategy,
+}
+
+impl Default for MatchOptions {
+    /// Scales 0.5..=2.0 in 0.1 steps, `INTER_LINEAR`, `TM_CCOEFF_NORMED`, color matching,
+    /// exhaustive search.
+    fn default() -> Self {
+        Self {
+            scale_min: 0.5,
+            scale_max: 2.0,
+            scale_step: 0.1,
+            interpolation: INTER_LINEAR,
+            method: MatchMethod::CcoeffNormed,
+            mode: MatchMode::Color,
+            strategy: SearchStrategy::Exhaustive,
+        }
+    }
+}
+
+impl MatchOptions {
+    /// Matches the template at its native size only (a single `match_template` pass).
+    pub fn single_scale() -> Self {
+        Self {
+            scale_min: 1.0,
+            scale_max: 1.0,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the scale range, both ends inclusive.
+    pub fn scales(mut self, min: f64, max: f64, step: f64) -> Self {
+        self.scale_min = min;
+        self.scale_max = max;
+        self.scale_step = step;
+        self
+    }
+
+    pub fn interpolation(mut self, interpolation: i32) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    pub fn method(mut self, method: MatchMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn mode(mut self, mode: MatchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn strategy(mut self, strategy: SearchStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    fn scale_values(&self) -> Result<Vec<f64>> {
+        let valid_range = self.scale_min > 0.0 && self.scale_min <= self.scale_max;
+        if !valid_range {
+            return Err(Error::InvalidArgument(format!(
+                "invalid scale range {}..={}",
+                self.scale_min, self.scale_max
+            )));
+        }
+        if self.scale_min == self.scale_max {
+            return Ok(vec![self.scale_min]);
+        }
+        let valid_step = self.scale_step > 0.0;
+        if !valid_step {
+            return Err(Error::InvalidArgument(format!(
+                "scale step must be greater than zero, got {}",
+                self.scale_step
+            )));
+        }
+
+        // Небольшой допуск, чтобы scale_max не терялся из-за ошибок округления
+        let steps = ((self.scale_max - self.scale_min) / self.scale_step + 1e-9).floor() as usize;
+        Ok((0..=steps)
+            .map(|i| self.scale_min + i as f64 * self.scale_step)
+            .collect())
+    }
+}
+
+/// Best location of a template in a source image.
+#[derive(Debug, Clone, Copy)]
+pub struct Match {
+    /// Matched area in source image coordinates.
+    pub area: DisplayArea,
+    /// Similarity score, higher is better (1.0 is a perfect match).
+    pub score: f64,
+    /// Scale that was applied to the template.
+    pub scale: f64,
+    pub method: MatchMethod,
+}
+
+impl Match {
+    /// Whether the score passes the `recognition` threshold.
+    pub fn is_recognized(&self, recognition: f32) -> bool {
+        self.score > recognition as f64
+    }
+}
+
+/// Ordering of the matches returned by [`find_all_targets`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MatchOrder {
+    /// Highest score first.
+    Score,
+    /// Rows from top to bottom, left to right inside a row.
+    ReadingOrder,
+}
+
+fn check_images(source: &Mat, target: &Mat) -> Result<()> {
     if source.empty() {
-        return Err("find_target_in_image: Source image is empty".into());
+        return Err(Error::EmptyImage("source"));
     }
     if target.empty() {
-        return Err("find_target_in_image: target image is empty".into());
+        return Err(Error::EmptyImage("target"));
     }
+    Ok(())
+}
 
-    if target.rows() > source.rows() || target.cols() > source.cols() {
-        return Err("find_target_in_image: target size exceeds source image dimensions".into());
+/// Error for a sweep in which no scaled template fitted into the source.
+fn larger_than_source(source: &Mat, target: &Mat, scale: f64) -> Error {
+    Error::TemplateLargerThanSource {
+        template: (
+            (target.cols() as f64 * scale).round() as i32,
+            (target.rows() as f64 * scale).round() as i32,
+        ),
+        source: (source.cols(), source.rows()),
     }
+}
 
-    if !(0.0..=1.0).contains(&recognition) {
-        return Err(format!(
-            "find_target_in_image: Threshold must be between 0 and 1, got {}",
-            recognition
-        ).into());
+/// Template split into its color channels and an optional alpha mask.
+struct Template {
+    image: Mat,
+    mask: Option<Mat>,
+}
+
+impl Template {
+    /// Uses the alpha channel of a BGRA template as the matching mask.
+    ///
+    /// Fully opaque templates are matched without a mask.
+    fn prepare(target: &Mat) -> Result<Self> {
+        if target.channels() != 4 {
+            return Ok(Self {
+                image: target.try_clone()?,
+                mask: None,
+            });
+        }
+
+        let mut image = Mat::default();
+        cvt_color(
+            target,
+            &mut image,
+            COLOR_BGRA2BGR,
+            0,
+            AlgorithmHint::ALGO_HINT_DEFAULT,
+        )?;
+        let mut alpha = Mat::default();
+        extract_channel(target, &mut alpha, 3)?;
+
+        let mut min_alpha = 0.0;
+        min_max_loc(&alpha, Some(&mut min_alpha), None, None, None, &Mat::default())?;
+        let mask = if min_alpha < 255.0 { Some(alpha) } else { None };
+
+        Ok(Self { image, mask })
     }
+}
 
-    // Определение диапазона масштабов от 0.5 до 2.0 с шагом 0.1
-    let scales: Vec<f64> = (5..=20).map(|i| i as f64 / 10.0).collect();
+/// Loads a template image keeping its alpha channel.
+///
+/// PNG icons with transparency are returned as BGRA, so the matching functions use
+/// the alpha channel as a mask and ignore whatever is behind transparent pixels.
+/// Grayscale images are converted to BGR.
+pub fn load_template(path: impl AsRef<Path>) -> Result<Mat> {
+    let path = path.as_ref();
+    let path_str = path.to_str().ok_or_else(|| {
+        Error::InvalidArgument(format!("template path is not valid UTF-8: {}", path.display()))
+    })?;
 
-    let mut best_val = -1.0;
-    let mut best_loc = Point::new(0, 0);
-    let mut best_scale = 1.0;
+    let image = imread(path_str, IMREAD_UNCHANGED)?;
+    if image.empty() {
+        return Err(Error::InvalidArgument(format!(
+            "can not decode template {}",
+            path.display()
+        )));
+    }
 
-    // Перебор масштабов
-    for scale in scales {
-        // Вычисление нового размера шаблона
-        let new_width = (target.cols() as f64 * scale).round() as i32;
-        let new_height = (target.rows() as f64 * scale).round() as i32;
-        if new_width <= 0 || new_height <= 0 {
-            continue;
-        }
+    if image.channels() == 1 {
+        let mut bgr = Mat::default();
+        cvt_color(
+            &image,
+            &mut bgr,
+            COLOR_GRAY2BGR,
+            0,
+            AlgorithmHint::ALGO_HINT_DEFAULT,
+        )?;
+        return Ok(bgr);
+    }
+    Ok(image)
+}
+
+/// Resizes `template` by `scale` and runs `match_template` against `source`.
+///
+/// `source` must already be preprocessed with `options.mode`; the template is
+/// preprocessed after resizing so that edge maps stay one pixel wide.
+/// The returned score map is "higher is better" for every method.
+/// Returns `None` when the scaled template is empty or does not fit into `source`.
+fn match_at_scale(
+    source: &Mat,
+    template: &Template,
+    scale: f64,
+    options: &MatchOptions,
+    method: MatchMethod,
+) -> Result<Option<(Mat, Size)>> {
+    let target = &template.image;
 
-        // Изменение размера шаблона
-        let mut resized_target = Mat::default();
+    // Вычисление нового размера шаблона
+    let new_width = (target.cols() as f64 * scale).round() as i32;
+    let new_height = (target.rows() as f64 * scale).round() as i32;
+    if new_width <= 0 || new_height <= 0 {
+        return Ok(None);
+    }
+
+    // Проверка, что шаблон не больше изображения
+    if new_width > source.cols() || new_height > source.rows() {
+        return Ok(None);
+    }
+
+    // Изменение размера шаблона
+    let mut resized_target = Mat::default();
+    resize(
+        &target,
+        &mut resized_target,
+        Size::new(new_width, new_height),
+        0.0,
+        0.0,
+        options.interpolation,
+    )?;
+    let resized_target = options.mode.apply(&resized_target)?;
+
+    // Маска масштабируется без интерполяции, чтобы остаться бинарной по краям
+    let mut resized_mask = Mat::default();
+    if let Some(mask) = &template.mask {
         resize(
-            &target,
-            &mut resized_target,
+            mask,
+            &mut resized_mask,
             Size::new(new_width, new_height),
             0.0,
             0.0,
-            INTER_LINEAR,
+            INTER_NEAREST,
         )?;
+    }
 
-        // Проверка, что шаблон не больше изображения
-        if resized_target.cols() > source.cols() || resized_target.rows() > source.rows() {
-            continue;
-        }
+    // Выполнение сопоставления шаблона
+    let mut result = Mat::default();
+    match_template(
+        &source,
+        &*resized_target,
+        &mut result,
+        method.to_opencv(),
+        &resized_mask,
+    )?;
 
-        // Выполнение сопоставления шаблона
-        let mut result = Mat::default();
-        match_template(
-            &source,
-            &resized_target,
-            &mut result,
-            TM_CCOEFF_NORMED,
-            &Mat::default(),
-        )?;
+    if template.mask.is_some() {
+        // С маской на однородных участках возможно деление на ноль
+        patch_na_ns(&mut result, 0.0)?;
+    }
+
+    if method == MatchMethod::SqdiffNormed {
+        // 0 - идеальное совпадение, переворачиваем: score = 1 - value
+        let mut inverted = Mat::default();
+        result.convert_to(&mut inverted, -1, -1.0, 1.0)?;
+        result = inverted;
+    }
+
+    Ok(Some((result, Size::new(new_width, new_height))))
+}
+
+/// Best location of a template found by a scale sweep.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    score: f64,
+    location: Point,
+    scale: f64,
+    /// Size of the scaled template that was matched.
+    size: Size,
+}
+
+/// Best candidate of `template` in `source` over `scales`.
+///
+/// `source` is raw, `options.mode` is applied here.
+fn sweep_scales(
+    source: &Mat,
+    template: &Template,
+    scales: &[f64],
+    options: &MatchOptions,
+    method: MatchMethod,
+) -> Result<Option<Candidate>> {
+    let source = options.mode.apply(source)?;
+    let source = &*source;
+
+    let mut best: Option<Candidate> = None;
+
+    // Перебор масштабов
+    for scale in scales.iter().copied() {
+        let Some((result, size)) = match_at_scale(source, template, scale, options, method)? else {
+            continue;
+        };
 
         // Поиск максимального значения и его местоположения
         let mut min_val = 0.0;
@@ -89,25 +469,289 @@ pub fn find_target_in_image(
         )?;
 
         // Обновление лучшего результата
-        if max_val > best_val {
-            best_val = max_val;
-            best_loc = max_loc;
-            best_scale = scale;
+        if best.is_none_or(|best| max_val > best.score) {
+            best = Some(Candidate {
+                score: max_val,
+                location: max_loc,
+                scale,
+                size,
+            });
         }
     }
 
-    // Проверка порога соответствия
-    if best_val > recognition as f64 {
+    Ok(best)
+}
+
+/// Smallest side, in pixels, of a downsampled template still worth matching.
+const MIN_PYRAMID_TEMPLATE_SIDE: i32 = 8;
 
-        Ok(DisplayArea::from_rectangle(
-            best_loc.x, 
-            best_loc.y, 
-            ( (target.cols() as f64) * best_scale) as u32, 
-            ( (target.rows() as f64) * best_scale) as u32
-        ))
+/// Coarse-to-fine search: full sweep on downsampled images, then a sweep over the
+/// neighbouring scales inside a small ROI of the full resolution source.
+///
+/// Falls back to the exhaustive sweep when the downsampled template would be
+/// too small to carry any detail.
+fn sweep_pyramid(
+    source: &Mat,
+    target: &Mat,
+    template: &Template,
+    scales: &[f64],
+    options: &MatchOptions,
+    method: MatchMethod,
+    levels: u32,
+) -> Result<Option<Candidate>> {
+    let factor = 0.5f64.powi(levels as i32);
+    let small_side = (target.cols().min(target.rows()) as f64 * factor * scales[0]).round() as i32;
+    if levels == 0 || small_side < MIN_PYRAMID_TEMPLATE_SIDE {
+        return sweep_scales(source, template, scales, options, method);
+    }
+
+    // Грубый поиск на уменьшенных изображениях
+    let small_source = downsample(source, factor)?;
+    let small_template = Template::prepare(&downsample(target, factor)?)?;
+    let Some(coarse) = sweep_scales(&small_source, &small_template, scales, options, method)?
+    else {
+        return Ok(None);
+    };
+
+    // Уточнение: соседние масштабы в окрестности найденной точки
+    let refine_scales: Vec<f64> = [
+        coarse.scale - options.scale_step,
+        coarse.scale,
+        coarse.scale + options.scale_step,
+    ]
+    .into_iter()
+    .filter(|scale| *scale > 0.0 && (options.scale_min..=options.scale_max).contains(scale))
+    .collect();
+    let largest_scale = refine_scales.iter().copied().fold(coarse.scale, f64::max);
+
+    // Погрешность позиции на грубом уровне - пара пикселей уменьшенного изображения.
+    // ROI сдвигается внутрь изображения, а не обрезается, чтобы у краёв в неё
+    // помещался шаблон любого из уточняющих масштабов
+    let margin = (2.0 / factor).ceil() as i32;
+    let roi_width = ((target.cols() as f64 * largest_scale).ceil() as i32 + 2 * margin)
+        .min(source.cols());
+    let roi_height = ((target.rows() as f64 * largest_scale).ceil() as i32 + 2 * margin)
+        .min(source.rows());
+    let roi_x = ((coarse.location.x as f64 / factor) as i32 - margin)
+        .clamp(0, source.cols() - roi_width);
+    let roi_y = ((coarse.location.y as f64 / factor) as i32 - margin)
+        .clamp(0, source.rows() - roi_height);
+    let roi = Mat::roi(source, Rect::new(roi_x, roi_y, roi_width, roi_height))?;
+
+    match sweep_scales(&roi, template, &refine_scales, options, method)? {
+        Some(fine) => Ok(Some(Candidate {
+            location: Point::new(fine.location.x + roi_x, fine.location.y + roi_y),
+            ..fine
+        })),
+        // Ни один уточняющий масштаб не поместился - полный перебор
+        None => sweep_scales(source, template, scales, options, method),
+    }
+}
+
+fn downsample(image: &Mat, factor: f64) -> Result<Mat> {
+    let mut small = Mat::default();
+    resize(image, &mut small, Size::new(0, 0), factor, factor, INTER_AREA)?;
+    Ok(small)
+}
+
+/// Finds the best location of `target` in `source` over the scale sweep.
+///
+/// Unlike [`find_target_in_image`] no threshold is applied: the best candidate is
+/// always returned, so near-misses can be logged and thresholds tuned per template.
+///
+/// # Errors
+/// Returns [`Error::TemplateLargerThanSource`] when the template does not fit into
+/// the source at any scale of `options`.
+pub fn find_best_match(
+    source: &Mat,
+    target: &Mat,
+    options: &MatchOptions,
+) -> Result<Match> {
 
+    check_images(source, target)?;
+    let scales = options.scale_values()?;
+    let template = Template::prepare(target)?;
+    let method = options.method;
+
+    let best = match options.strategy {
+        SearchStrategy::Exhaustive => {
+            sweep_scales(source, &template, &scales, options, method)?
+        }
+        SearchStrategy::Pyramid { levels } => {
+            sweep_pyramid(source, target, &template, &scales, options, method, levels)?
+        }
+    };
+
+    let Some(best) = best else {
+        return Err(larger_than_source(source, target, scales[0]));
+    };
+
+    Ok(Match {
+        area: DisplayArea::from_rectangle(
+            best.location.x,
+            best.location.y,
+            best.size.width as u32,
+            best.size.height as u32,
+        ),
+        score: best.score,
+        scale: best.scale,
+        method,
+    })
+}
+
+/// Finds every occurrence of `target` in `source` scoring above `recognition`.
+///
+/// # Arguments
+/// * `recognition` - Score threshold in `0.0..=1.0`
+/// * `source` - Image to search in
+/// * `target` - Template to search for
+/// * `options` - Scale sweep and matching method
+/// * `max_overlap` - Maximum IoU (intersection over union) allowed between two
+///   returned matches; overlapping candidates with a lower score are suppressed
+/// * `order` - Ordering of the returned matches
+///
+/// # Behavior
+/// - Local maxima above the threshold are collected at every scale of the sweep
+/// - Candidates are de-duplicated across scales with non-maximum suppression
+/// - An empty vector is returned when nothing passes the threshold
+pub fn find_all_targets(
+    recognition: f32,
+    source: &Mat,
+    target: &Mat,
+    options: &MatchOptions,
+    max_overlap: f64,
+    order: MatchOrder,
+) -> Result<Vec<Match>> {
+
+    if !(0.0..=1.0).contains(&recognition) {
+        return Err(Error::InvalidThreshold(recognition));
+    }
+    check_images(source, target)?;
+    let scales = options.scale_values()?;
+    let template = Template::prepare(target)?;
+    let method = options.method;
+    let source = options.mode.apply(source)?;
+    let source = &*source;
+
+    let mut candidates: Vec<Match> = Vec::new();
+    let mut matched_any_scale = false;
+
+    for scale in scales.iter().copied() {
+        let Some((result, size)) = match_at_scale(source, &template, scale, options, method)? else {
+            continue;
+        };
+        matched_any_scale = true;
+
+        // Локальные максимумы: значение совпадает с максимумом окрестности 3x3
+        let mut dilated = Mat::default();
+        dilate(
+            &result,
+            &mut dilated,
+            &Mat::default(),
+            Point::new(-1, -1),
+            1,
+            BORDER_CONSTANT,
+            morphology_default_border_value()?,
+        )?;
+
+        let values = result.data_typed::<f32>()?;
+        let maxima = dilated.data_typed::<f32>()?;
+        let cols = result.cols() as usize;
+        for (index, (&value, &maximum)) in values.iter().zip(maxima).enumerate() {
+            if value > recognition && value >= maximum {
+                candidates.push(Match {
+                    area: DisplayArea::from_rectangle(
+                        (index % cols) as i32,
+                        (index / cols) as i32,
+                        size.width as u32,
+                        size.height as u32,
+                    ),
+                    score: value as f64,
+                    scale,
+                    method,
+                });
+            }
+        }
+    }
+
+    if !matched_any_scale {
+        return Err(larger_than_source(source, target, scales[0]));
+    }
+
+    let mut matches = non_maximum_suppression(candidates, max_overlap);
+    if order == MatchOrder::ReadingOrder {
+        sort_reading_order(&mut matches);
+    }
+    Ok(matches)
+}
+
+/// Keeps the best scoring matches whose mutual IoU does not exceed `max_overlap`.
+///
+/// The result is sorted by score, highest first.
+fn non_maximum_suppression(mut candidates: Vec<Match>, max_overlap: f64) -> Vec<Match> {
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let mut kept: Vec<Match> = Vec::new();
+    for candidate in candidates {
+        if kept
+            .iter()
+            .all(|other| candidate.area.iou(&other.area) <= max_overlap)
+        {
+            kept.push(candidate);
+        }
+    }
+    kept
+}
+
+/// Sorts matches into rows (top to bottom) and by x inside a row.
+///
+/// Two matches share a row when their vertical centers differ by less than
+/// half of the smaller height.
+fn sort_reading_order(matches: &mut [Match]) {
+    matches.sort_by_key(|m| m.area.get_average_point().1);
+
+    let mut rows: Vec<usize> = Vec::with_capacity(matches.len());
+    let mut row = 0;
+    let mut row_center = None;
+    for m in matches.iter() {
+        let (_, center_y) = m.area.get_average_point();
+        let (_, (_, height)) = m.area.get_rectangle();
+        if let Some((y, row_height)) = row_center {
+            if (center_y - y) as f64 >= (height.min(row_height) as f64) / 2.0 {
+                row += 1;
+                row_center = Some((center_y, height));
+            }
+        } else {
+            row_center = Some((center_y, height));
+        }
+        rows.push(row);
+    }
+
+    let mut keyed: Vec<(usize, Match)> = rows.into_iter().zip(matches.iter().copied()).collect();
+    keyed.sort_by_key(|(row, m)| (*row, m.area.get_points().0.0));
+    for (slot, (_, m)) in matches.iter_mut().zip(keyed) {
+        *slot = m;
+    }
+}
+
+pub fn find_target_in_image(
+    recognition: f32,
+    source: &Mat,
+    target: &Mat,
+    options: &MatchOptions,
+) -> Result<DisplayArea> {
+
+    if !(0.0..=1.0).contains(&recognition) {
+        return Err(Error::InvalidThreshold(recognition));
+    }
+
+    let best = find_best_match(source, target, options)?;
+
+    // Проверка порога соответствия
+    if best.is_recognized(recognition) {
+        Ok(best.area)
     } else {
-        Err("Not founded".into())
+        Err(Error::TemplateNotFound { best_score: best.score })
     }
 }
 
@@ -115,21 +759,25 @@ pub fn is_target_on_image(
     recognition: f32,
     source: &Mat,
     target: &Mat,
+    options: &MatchOptions,
 )
-->Result<bool,Box<dyn std::error::Error>> {
+->Result<bool> {
 
     match find_target_in_image(
         recognition,
         source, 
         target, 
+        options,
     ){
         Ok(_)=>{
             return Ok(true);
         }
-        Err(e)=>{
-            println!("find_target_in_image: Error ocurated in is_target_on_image: {}",e);
+        Err(Error::TemplateNotFound { .. })=>{
             return Ok(false);
         }
+        Err(e)=>{
+            return Err(e);
+        }
     }
 
 }
@@ -137,3 +785,160 @@ pub fn is_target_on_image(
 
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencv::core::{self, CV_8UC3, CV_8UC4, Scalar, Vec3b, Vec4b};
+    use opencv::imgproc;
+
+    fn candidate(x: i32, y: i32, w: u32, h: u32, score: f64) -> Match {
+        Match {
+            area: DisplayArea::from_rectangle(x, y, w, h),
+            score,
+            scale: 1.0,
+            method: MatchMethod::CcoeffNormed,
+        }
+    }
+
+    fn areas(matches: &[Match]) -> Vec<((i32, i32), (u32, u32))> {
+        matches.iter().map(|m| m.area.get_rectangle()).collect()
+    }
+
+    #[test]
+    fn non_maximum_suppression_keeps_the_best_of_overlapping_matches() {
+        let candidates = vec![
+            candidate(0, 0, 40, 20, 0.90),
+            candidate(4, 0, 40, 20, 0.95),
+            candidate(100, 0, 40, 20, 0.85),
+            candidate(20, 0, 40, 20, 0.80),
+        ];
+
+        // IoU с лучшим совпадением: 0.82 у первого, 0.43 у последнего
+        assert_eq!(
+            areas(&non_maximum_suppression(candidates.clone(), 0.3)),
+            vec![((4, 0), (40, 20)), ((100, 0), (40, 20))]
+        );
+        assert_eq!(
+            areas(&non_maximum_suppression(candidates.clone(), 0.5)),
+            vec![
+                ((4, 0), (40, 20)),
+                ((100, 0), (40, 20)),
+                ((20, 0), (40, 20)),
+            ]
+        );
+        assert_eq!(non_maximum_suppression(candidates, 1.0).len(), 4);
+    }
+
+    #[test]
+    fn non_maximum_suppression_of_no_candidates() {
+        assert!(non_maximum_suppression(Vec::new(), 0.3).is_empty());
+    }
+
+    #[test]
+    fn sort_reading_order_groups_jittered_rows() {
+        let mut matches = [
+            candidate(200, 12, 30, 30, 0.9),
+            candidate(150, 60, 30, 30, 0.9),
+            candidate(10, 10, 30, 30, 0.9),
+            candidate(20, 58, 30, 30, 0.9),
+            candidate(100, 14, 30, 30, 0.9),
+        ];
+
+        sort_reading_order(&mut matches);
+
+        assert_eq!(
+            areas(&matches),
+            vec![
+                ((10, 10), (30, 30)),
+                ((100, 14), (30, 30)),
+                ((200, 12), (30, 30)),
+                ((20, 58), (30, 30)),
+                ((150, 60), (30, 30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_reading_order_starts_a_row_at_half_the_height() {
+        let mut matches = [
+            candidate(0, 15, 30, 30, 0.9),
+            candidate(100, 0, 30, 30, 0.9),
+        ];
+
+        sort_reading_order(&mut matches);
+
+        assert_eq!(
+            areas(&matches),
+            vec![((100, 0), (30, 30)), ((0, 15), (30, 30))]
+        );
+
+        let mut matches = [
+            candidate(0, 14, 30, 30, 0.9),
+            candidate(100, 0, 30, 30, 0.9),
+        ];
+
+        sort_reading_order(&mut matches);
+
+        assert_eq!(
+            areas(&matches),
+            vec![((0, 14), (30, 30)), ((100, 0), (30, 30))]
+        );
+    }
+
+    /// Blurred noise: every patch of it is unique.
+    fn noise(width: i32, height: i32) -> Mat {
+        let mut noise =
+            Mat::new_rows_cols_with_default(height, width, CV_8UC3, Scalar::all(0.0)).unwrap();
+        core::randu(&mut noise, &Scalar::all(0.0), &Scalar::all(255.0)).unwrap();
+        let mut blurred = Mat::default();
+        imgproc::gaussian_blur_def(&noise, &mut blurred, Size::new(0, 0), 2.0).unwrap();
+        blurred
+    }
+
+    /// A 40x40 BGRA icon: a textured opaque disc with transparent corners.
+    fn alpha_icon() -> Mat {
+        let color = noise(40, 40);
+        let mut icon = Mat::new_rows_cols_with_default(40, 40, CV_8UC4, Scalar::all(0.0)).unwrap();
+        for row in 0..40 {
+            for col in 0..40 {
+                if (row - 20) * (row - 20) + (col - 20) * (col - 20) <= 16 * 16 {
+                    let [b, g, r] = color.at_2d::<Vec3b>(row, col).unwrap().0;
+                    *icon.at_2d_mut::<Vec4b>(row, col).unwrap() = Vec4b::from([b, g, r, 255]);
+                }
+            }
+        }
+        icon
+    }
+
+    /// Draws the opaque pixels of `icon` onto a copy of `background` at (x, y).
+    fn composite(background: &Mat, icon: &Mat, x: i32, y: i32) -> Mat {
+        let mut screen = background.try_clone().unwrap();
+        for row in 0..icon.rows() {
+            for col in 0..icon.cols() {
+                let [b, g, r, a] = icon.at_2d::<Vec4b>(row, col).unwrap().0;
+                if a == 255 {
+                    *screen.at_2d_mut::<Vec3b>(y + row, x + col).unwrap() = Vec3b::from([b, g, r]);
+                }
+            }
+        }
+        screen
+    }
+
+    #[test]
+    fn alpha_icon_matches_on_any_background() {
+        let icon = alpha_icon();
+        // Прозрачные углы иконки на разных фонах разные
+        let backgrounds = [noise(200, 150), noise(200, 150)];
+
+        for background in &backgrounds {
+            let screen = composite(background, &icon, 70, 50);
+
+            let found = find_best_match(&screen, &icon, &MatchOptions::single_scale()).unwrap();
+
+            assert_eq!(found.area.get_rectangle(), ((70, 50), (40, 40)));
+            assert_eq!(found.method, MatchMethod::CcoeffNormed);
+            assert!(found.score > 0.99, "{}", found.score);
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use opencv::{
+    core::{
+        BORDER_CONSTANT, BORDER_REPLICATE, Mat, Point, Point2f, Scalar, Size, Vector, bitwise_not,
+        copy_make_border, find_non_zero, no_array,
+    },
+    imgproc::{
+        ADAPTIVE_THRESH_GAUSSIAN_C, INTER_CUBIC, THRESH_BINARY, THRESH_BINARY_INV, THRESH_OTSU,
+        adaptive_threshold, get_rotation_matrix_2d, median_blur, min_area_rect, resize, threshold,
+        warp_affine,
+    },
+    prelude::*,
+};
+
+use super::ocv::to_grayscale;
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+/// A single image operation of a [`Preprocessor`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreprocessStep {
+    /// Resizes by the factor; tesseract reads text best at 20-40 px cap height,
+    /// UI text is usually 8-12 px.
+    Upscale(f64),
+    /// Converts to a single channel.
+    Grayscale,
+    /// Binarizes against the Gaussian-weighted mean of each `block_size` neighbourhood
+    /// minus `c`, which copes with gradients and uneven backgrounds. Converts to
+    /// grayscale first if needed.
+    AdaptiveThreshold { block_size: i32, c: f64 },
+    /// Inverts the colors, turning light text on a dark theme into dark on light.
+    Invert,
+    /// Median blur with an odd `kernel` size, removes speckles and JPEG noise.
+    Denoise { kernel: i32 },
+    /// Rotates the text back to horizontal, estimated from the dark pixels.
+    /// The image size is kept.
+    Deskew,
+    /// Adds a border of `pixels` filled with `value`; tesseract misses text touching
+    /// the image edge.
+    Pad { pixels: u32, value: u8 },
+}
+
+/// Chain of [`PreprocessStep`]s applied to an image before OCR.
+///
+/// ```ignore
+/// let preprocessor = Preprocessor::new().upscale(3.0).grayscale().invert().pad(10, 255);
+/// let options = OcrOptions::default().preprocess(preprocessor);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Preprocessor {
+    pub steps: Vec<PreprocessStep>,
+}
+
+impl Preprocessor {
+    /// An empty pipeline, the image is passed through as is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dark text of a label or button on a light, possibly gradient, background.
+    pub fn ui_label() -> Self {
+        Self::new()
+            .upscale(2.0)
+            .grayscale()
+            .denoise(3)
+            .adaptive_threshold(31, 15.0)
+            .pad(16, 255)
+    }
+
+    /// Digits of a price, counter or timer; pair with a single-line page segmentation
+    /// mode and a digit whitelist in [`super::tsrt::OcrOptions`].
+    pub fn numeric_field() -> Self {
+        Self::new()
+            .upscale(3.0)
+            .grayscale()
+            .adaptive_threshold(21, 10.0)
+            .denoise(3)
+            .pad(16, 255)
+    }
+
+    /// Light text on a dark theme.
+    pub fn dark_background() -> Self {
+        Self::new()
+            .upscale(2.0)
+            .grayscale()
+            .invert()
+            .adaptive_threshold(31, 15.0)
+            .pad(16, 255)
+    }
+
+    pub fn step(mut self, step: PreprocessStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn upscale(self, factor: f64) -> Self {
+        self.step(PreprocessStep::Upscale(factor))
+    }
+
+    pub fn grayscale(self) -> Self {
+        self.step(PreprocessStep::Grayscale)
+    }
+
+    pub fn adaptive_threshold(self, block_size: i32, c: f64) -> Self {
+        self.step(PreprocessStep::AdaptiveThreshold { block_size, c })
+    }
+
+    pub fn invert(self) -> Self {
+        self.step(PreprocessStep::Invert)
+    }
+
+    pub fn denoise(self, kernel: i32) -> Self {
+        self.step(PreprocessStep::Denoise { kernel })
+    }
+
+    pub fn deskew(self) -> Self {
+        self.step(PreprocessStep::Deskew)
+    }
+
+    pub fn pad(self, pixels: u32, value: u8) -> Self {
+        self.step(PreprocessStep::Pad { pixels, value })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Runs the steps in order.
+    ///
+    /// # Errors
+    /// - [`Error::EmptyImage`] if `image` is empty
+    /// - [`Error::InvalidArgument`] for a non-positive upscale factor, an even or
+    ///   too small block size or kernel
+    pub fn apply(&self, image: &Mat) -> Result<Mat> {
+        if image.empty() {
+            return Err(Error::EmptyImage("preprocess image"));
+        }
+
+        let mut image = image.try_clone()?;
+        for step in &self.steps {
+            image = apply_step(step, &image)?;
+        }
+        Ok(image)
+    }
+
+    /// Maps an area of the preprocessed image back to the original image.
+    ///
+    /// Undoes upscaling and padding; the rotation of [`PreprocessStep::Deskew`] is
+    /// small and not undone.
+    pub fn to_original(&self, area: &DisplayArea) -> DisplayArea {
+        // x' = x * scale + offset
+        let (scale, offset) =
+            self.steps
+                .iter()
+                .fold((1.0, 0.0), |(scale, offset), step| match step {
+                    PreprocessStep::Upscale(factor) => (scale * factor, offset * factor),
+                    PreprocessStep::Pad { pixels, .. } => (scale, offset + *pixels as f64),
+                    _ => (scale, offset),
+                });
+
+        let ((s_x, s_y), (e_x, e_y)) = area.get_points();
+        let map = |value: i32| ((value as f64 - offset) / scale).round() as i32;
+        DisplayArea::from_points(map(s_x), map(s_y), map(e_x), map(e_y))
+    }
+}
+
+fn apply_step(step: &PreprocessStep, image: &Mat) -> Result<Mat> {
+    let mut result = Mat::default();
+    match *step {
+        PreprocessStep::Upscale(factor) => {
+            let valid_factor = factor > 0.0;
+            if !valid_factor {
+                return Err(Error::InvalidArgument(format!(
+                    "upscale factor must be positive, got {}",
+                    factor
+                )));
+            }
+            resize(
+                image,
+                &mut result,
+                Size::new(0, 0),
+                factor,
+                factor,
+                INTER_CUBIC,
+            )?;
+        }
+        PreprocessStep::Grayscale => result = to_grayscale(image)?,
+        PreprocessStep::AdaptiveThreshold { block_size, c } => {
+            if block_size < 3 || block_size % 2 == 0 {
+                return Err(Error::InvalidArgument(format!(
+                    "threshold block size must be odd and at least 3, got {}",
+                    block_size
+                )));
+            }
+            adaptive_threshold(
+                &to_grayscale(image)?,
+                &mut result,
+                255.0,
+                ADAPTIVE_THRESH_GAUSSIAN_C,
+                THRESH_BINARY,
+                block_size,
+                c,
+            )?;
+        }
+        PreprocessStep::Invert => bitwise_not(image, &mut result, &no_array())?,
+        PreprocessStep::Denoise { kernel } => {
+            if kernel < 3 || kernel % 2 == 0 {
+                return Err(Error::InvalidArgument(format!(
+                    "denoise kernel must be odd and at least 3, got {}",
+                    kernel
+                )));
+            }
+            median_blur(image, &mut result, kernel)?;
+        }
+        PreprocessStep::Deskew => result = deskew(image)?,
+        PreprocessStep::Pad { pixels, value } => {
+            let pixels = pixels as i32;
+            copy_make_border(
+                image,
+                &mut result,
+                pixels,
+                pixels,
+                pixels,
+                pixels,
+                BORDER_CONSTANT,
+                Scalar::all(value as f64),
+            )?;
+        }
+    }
+    Ok(result)
+}
+
+/// Rotates `image` by the skew angle of its dark pixels.
+fn deskew(image: &Mat) -> Result<Mat> {
+    // Тёмные пиксели текста на светлом фоне
+    let mut binary = Mat::default();
+    threshold(
+        &to_grayscale(image)?,
+        &mut binary,
+        0.0,
+        255.0,
+        THRESH_BINARY_INV | THRESH_OTSU,
+    )?;
+
+    let mut points = Vector::<Point>::new();
+    find_non_zero(&binary, &mut points)?;
+    if points.len() < 2 {
+        return Ok(image.try_clone()?);
+    }
+
+    // minAreaRect возвращает угол в (0, 90]
+    let mut angle = min_area_rect(&points)?.angle;
+    if angle > 45.0 {
+        angle -= 90.0;
+    }
+    if angle.abs() < 0.1 {
+        return Ok(image.try_clone()?);
+    }
+
+    let center = Point2f::new(image.cols() as f32 / 2.0, image.rows() as f32 / 2.0);
+    let rotation = get_rotation_matrix_2d(center, angle as f64, 1.0)?;
+    let mut rotated = Mat::default();
+    warp_affine(
+        image,
+        &mut rotated,
+        &rotation,
+        image.size()?,
+        INTER_CUBIC,
+        BORDER_REPLICATE,
+        Scalar::default(),
+    )?;
+    Ok(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opencv::core::CV_8UC3;
+    use opencv::imgproc::{FONT_HERSHEY_SIMPLEX, LINE_8, put_text};
+
+    /// A 120x40 screenshot of the text `1024` in `text` color on `background`.
+    fn label(background: f64, text: f64) -> Mat {
+        let mut image =
+            Mat::new_rows_cols_with_default(40, 120, CV_8UC3, Scalar::all(background)).unwrap();
+        put_text(
+            &mut image,
+            "1024",
+            Point::new(10, 30),
+            FONT_HERSHEY_SIMPLEX,
+            0.8,
+            Scalar::all(text),
+            1,
+            LINE_8,
+            false,
+        )
+        .unwrap();
+        image
+    }
+
+    #[test]
+    fn to_original_undoes_pad_then_upscale() {
+        let preprocessor = Preprocessor::new().pad(10, 255).upscale(2.0);
+
+        let processed = preprocessor.apply(&label(230.0, 30.0)).unwrap();
+
+        // (x + 10) * 2
+        assert_eq!((processed.cols(), processed.rows()), (280, 120));
+        assert_eq!(
+            preprocessor.to_original(&DisplayArea::from_rectangle(40, 60, 100, 50)),
+            DisplayArea::from_rectangle(10, 20, 50, 25)
+        );
+        assert_eq!(
+            preprocessor.to_original(&DisplayArea::from_rectangle(20, 20, 240, 80)),
+            DisplayArea::from_rectangle(0, 0, 120, 40)
+        );
+    }
+
+    #[test]
+    fn to_original_undoes_upscale_then_pad() {
+        let preprocessor = Preprocessor::new().upscale(3.0).grayscale().pad(16, 255);
+
+        let processed = preprocessor.apply(&label(230.0, 30.0)).unwrap();
+
+        // x * 3 + 16
+        assert_eq!((processed.cols(), processed.rows()), (392, 152));
+        assert_eq!(
+            preprocessor.to_original(&DisplayArea::from_rectangle(46, 76, 60, 30)),
+            DisplayArea::from_rectangle(10, 20, 20, 10)
+        );
+        assert_eq!(
+            preprocessor.to_original(&DisplayArea::from_rectangle(16, 16, 360, 120)),
+            DisplayArea::from_rectangle(0, 0, 120, 40)
+        );
+    }
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        let image = label(230.0, 30.0);
+
+        for preprocessor in [
+            Preprocessor::new().upscale(0.0),
+            Preprocessor::new().upscale(-2.0),
+            Preprocessor::new().adaptive_threshold(1, 10.0),
+            Preprocessor::new().adaptive_threshold(20, 10.0),
+            Preprocessor::new().denoise(1),
+            Preprocessor::new().denoise(4),
+        ] {
+            assert!(
+                matches!(preprocessor.apply(&image), Err(Error::InvalidArgument(_))),
+                "{:?}",
+                preprocessor
+            );
+        }
+        assert!(matches!(
+            Preprocessor::ui_label().apply(&Mat::default()),
+            Err(Error::EmptyImage(_))
+        ));
+    }
+
+    #[test]
+    fn presets_produce_dark_text_on_a_white_page() {
+        for (preprocessor, image, factor) in [
+            (Preprocessor::ui_label(), label(230.0, 30.0), 2),
+            (Preprocessor::numeric_field(), label(230.0, 30.0), 3),
+            (Preprocessor::dark_background(), label(30.0, 230.0), 2),
+        ] {
+            let processed = preprocessor.apply(&image).unwrap();
+
+            assert_eq!(processed.channels(), 1, "{:?}", preprocessor);
+            assert_eq!(
+                (processed.cols(), processed.rows()),
+                (120 * factor + 32, 40 * factor + 32),
+                "{:?}",
+                preprocessor
+            );
+            let pixels = processed.data_bytes().unwrap();
+            assert!(
+                pixels.iter().all(|&value| value == 0 || value == 255),
+                "{:?}",
+                preprocessor
+            );
+            let text = pixels.iter().filter(|&&value| value == 0).count();
+            // Текст занимает малую часть страницы, фон белый
+            assert!(
+                text > 0 && text < pixels.len() / 4,
+                "{:?}: {} of {}",
+                preprocessor,
+                text,
+                pixels.len()
+            );
+            assert_eq!(*processed.at_2d::<u8>(0, 0).unwrap(), 255);
+        }
+    }
+
+    #[test]
+    fn empty_pipeline_keeps_the_image() {
+        let image = label(230.0, 30.0);
+        let preprocessor = Preprocessor::new();
+
+        let processed = preprocessor.apply(&image).unwrap();
+
+        assert!(preprocessor.is_empty());
+        assert_eq!(processed.data_bytes().unwrap(), image.data_bytes().unwrap());
+        let area = DisplayArea::from_rectangle(5, 6, 7, 8);
+        assert_eq!(preprocessor.to_original(&area), area);
+    }
+}
@@ -0,0 +1,355 @@
+use regex::{Regex, RegexBuilder};
+
+use super::ocr_result::{OcrLine, OcrResult, OcrWord};
+use crate::error::{Error, Result};
+use crate::utils::DisplayArea;
+
+/// How the text of a [`TextQuery`] is compared with the recognized words.
+#[derive(Debug, Clone)]
+pub enum TextPattern {
+    /// The words equal the text, whitespace collapsed.
+    Exact(String),
+    /// The words are within `max_distance` Levenshtein edits of the text, which
+    /// tolerates the odd misread character (`0`/`O`, `l`/`I`).
+    Fuzzy { text: String, max_distance: usize },
+    /// The regex matches somewhere in a line; case-insensitively unless the query
+    /// is case sensitive.
+    Regex(Regex),
+}
+
+/// Text to look for in an [`OcrResult`].
+///
+/// Exact and fuzzy queries of N words are compared with every run of N consecutive
+/// words of a line, so `"Save as"` is found in the line `"File Save as Close"`.
+/// A regex is searched in the whole text of each line.
+///
+/// Every query is case-insensitive unless [`TextQuery::case_sensitive`] says otherwise.
+#[derive(Debug, Clone)]
+pub struct TextQuery {
+    pattern: TextPattern,
+    case_sensitive: bool,
+    // Вариант регулярного выражения без учёта регистра
+    regex_ignore_case: Option<Regex>,
+}
+
+/// Text found by a [`TextQuery`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextMatch {
+    /// The recognized text that matched.
+    pub text: String,
+    /// Union of the areas of the matched words.
+    pub area: DisplayArea,
+    /// Mean confidence of the matched words, `0.0..=100.0`.
+    pub confidence: f32,
+    /// Levenshtein distance to the query, 0 for exact and regex queries.
+    pub distance: usize,
+}
+
+impl TextQuery {
+    /// Case-insensitive exact query.
+    pub fn exact(text: &str) -> Self {
+        Self {
+            pattern: TextPattern::Exact(text.to_string()),
+            case_sensitive: false,
+            regex_ignore_case: None,
+        }
+    }
+
+    /// Case-insensitive query tolerating up to `max_distance` edits.
+    pub fn fuzzy(text: &str, max_distance: usize) -> Self {
+        Self {
+            pattern: TextPattern::Fuzzy {
+                text: text.to_string(),
+                max_distance,
+            },
+            case_sensitive: false,
+            regex_ignore_case: None,
+        }
+    }
+
+    /// Case-insensitive regex query, e.g. `r"^total:?$"` or `r"\d+ items"`.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidArgument`] if `pattern` is not a valid regex.
+    pub fn regex(pattern: &str) -> Result<Self> {
+        let build = |ignore_case: bool| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+                .map_err(|e| Error::InvalidArgument(format!("text regex: {}", e)))
+        };
+        Ok(Self {
+            pattern: TextPattern::Regex(build(false)?),
+            case_sensitive: false,
+            regex_ignore_case: Some(build(true)?),
+        })
+    }
+
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    pub fn pattern(&self) -> &TextPattern {
+        &self.pattern
+    }
+
+    pub fn is_case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// Every match, line by line in reading order.
+    pub fn find_all(&self, result: &OcrResult) -> Vec<TextMatch> {
+        result
+            .lines()
+            .flat_map(|line| self.find_in_line(line))
+            .collect()
+    }
+
+    /// The closest match: the smallest distance, then the highest confidence,
+    /// then the first in reading order.
+    pub fn find(&self, result: &OcrResult) -> Option<TextMatch> {
+        self.find_all(result).into_iter().reduce(|best, candidate| {
+            let better = candidate.distance < best.distance
+                || (candidate.distance == best.distance && candidate.confidence > best.confidence);
+            if better { candidate } else { best }
+        })
+    }
+
+    fn find_in_line(&self, line: &OcrLine) -> Vec<TextMatch> {
+        let (text, max_distance) = match &self.pattern {
+            TextPattern::Regex(regex) => {
+                let regex = match &self.regex_ignore_case {
+                    Some(ignore_case) if !self.case_sensitive => ignore_case,
+                    _ => regex,
+                };
+                return find_regex(regex, line);
+            }
+            TextPattern::Exact(text) => (text, 0),
+            TextPattern::Fuzzy { text, max_distance } => (text, *max_distance),
+        };
+
+        let query = self.normalize(text);
+        let count = query.split(' ').count();
+        if query.is_empty() || line.words.len() < count {
+            return Vec::new();
+        }
+
+        line.words
+            .windows(count)
+            .filter_map(|words| {
+                let text = join_words(words);
+                let distance = levenshtein(&self.normalize(&text), &query);
+                (distance <= max_distance).then(|| text_match(words, text, distance))
+            })
+            .collect()
+    }
+
+    /// Collapses whitespace and folds the case unless the query is case sensitive.
+    fn normalize(&self, text: &str) -> String {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if self.case_sensitive {
+            text
+        } else {
+            text.to_lowercase()
+        }
+    }
+}
+
+fn find_regex(regex: &Regex, line: &OcrLine) -> Vec<TextMatch> {
+    let text = line.text();
+
+    // Байтовые диапазоны слов в тексте строки
+    let mut spans = Vec::with_capacity(line.words.len());
+    let mut start = 0;
+    for word in &line.words {
+        spans.push(start..start + word.text.len());
+        start += word.text.len() + 1;
+    }
+
+    regex
+        .find_iter(&text)
+        .filter(|found| !found.is_empty())
+        .filter_map(|found| {
+            let first = spans.iter().position(|span| span.end > found.start())?;
+            let last = spans.iter().rposition(|span| span.start < found.end())?;
+            (first <= last)
+                .then(|| text_match(&line.words[first..=last], found.as_str().to_string(), 0))
+        })
+        .collect()
+}
+
+fn join_words(words: &[OcrWord]) -> String {
+    words
+        .iter()
+        .map(|word| word.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `words` must not be empty.
+fn text_match(words: &[OcrWord], text: String, distance: usize) -> TextMatch {
+    let area = words[1..]
+        .iter()
+        .fold(words[0].area, |area, word| area.union(&word.area));
+    let confidence = words.iter().map(|word| word.confidence).sum::<f32>() / words.len() as f32;
+
+    TextMatch {
+        text,
+        area,
+        confidence,
+        distance,
+    }
+}
+
+/// Levenshtein distance between `a` and `b` in characters.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vision::ocr_result::OcrBlock;
+
+    /// A block with one line per entry; words are 20 px high, 10 px per character
+    /// and 10 px apart.
+    fn ocr(lines: &[&[(&str, f32)]]) -> OcrResult {
+        let lines = lines
+            .iter()
+            .enumerate()
+            .map(|(row, words)| {
+                let y = row as i32 * 30;
+                let mut x = 0;
+                let words: Vec<_> = words
+                    .iter()
+                    .map(|&(text, confidence)| {
+                        let width = text.chars().count() as u32 * 10;
+                        let area = DisplayArea::from_rectangle(x, y, width, 20);
+                        x += width as i32 + 10;
+                        OcrWord {
+                            text: text.to_string(),
+                            area,
+                            confidence,
+                        }
+                    })
+                    .collect();
+                OcrLine {
+                    area: DisplayArea::from_rectangle(0, y, x as u32, 20),
+                    words,
+                }
+            })
+            .collect();
+
+        OcrResult {
+            blocks: vec![OcrBlock {
+                area: DisplayArea::from_rectangle(0, 0, 400, 300),
+                lines,
+            }],
+        }
+    }
+
+    fn toolbar() -> OcrResult {
+        ocr(&[
+            &[
+                ("File", 95.0),
+                ("Save", 90.0),
+                ("as", 80.0),
+                ("Close", 95.0),
+            ],
+            &[("Total:", 90.0), ("42", 85.0), ("items", 95.0)],
+        ])
+    }
+
+    #[test]
+    fn levenshtein_counts_characters() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("Save", "Save"), 0);
+        assert_eq!(levenshtein("Sa ve", "Save"), 1);
+        assert_eq!(levenshtein("ёлка", "елка"), 1);
+    }
+
+    #[test]
+    fn exact_query_matches_consecutive_words() {
+        let found = TextQuery::exact("save  AS").find(&toolbar()).unwrap();
+
+        assert_eq!(
+            found,
+            TextMatch {
+                text: "Save as".to_string(),
+                area: DisplayArea::from_points(50, 0, 120, 20),
+                confidence: 85.0,
+                distance: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn exact_query_does_not_match_part_of_a_word() {
+        assert_eq!(TextQuery::exact("Sav").find(&toolbar()), None);
+        assert_eq!(TextQuery::exact("as Close File").find(&toolbar()), None);
+        assert_eq!(
+            TextQuery::exact("save as")
+                .case_sensitive(true)
+                .find(&toolbar()),
+            None
+        );
+    }
+
+    #[test]
+    fn fuzzy_query_tolerates_one_misread_character() {
+        let result = ocr(&[&[("C1ose", 90.0)]]);
+
+        let found = TextQuery::fuzzy("Close", 1).find(&result).unwrap();
+
+        assert_eq!(found.text, "C1ose");
+        assert_eq!(found.distance, 1);
+        assert_eq!(TextQuery::fuzzy("Close", 0).find(&result), None);
+        assert_eq!(TextQuery::fuzzy("Clone", 1).find_all(&result).len(), 0);
+    }
+
+    #[test]
+    fn find_prefers_the_closest_then_the_most_confident_match() {
+        let result = ocr(&[&[("Sove", 99.0)], &[("Save", 60.0)], &[("Save", 70.0)]]);
+
+        let found = TextQuery::fuzzy("save", 1).find(&result).unwrap();
+
+        assert_eq!(found.distance, 0);
+        assert_eq!(found.confidence, 70.0);
+        assert_eq!(found.area, DisplayArea::from_rectangle(0, 60, 40, 20));
+    }
+
+    #[test]
+    fn regex_query_maps_a_match_spanning_two_words() {
+        let query = TextQuery::regex(r"\d+ ITEMS").unwrap();
+
+        let found = query.find(&toolbar()).unwrap();
+
+        assert_eq!(found.text, "42 items");
+        assert_eq!(found.area, DisplayArea::from_points(70, 30, 150, 50));
+        assert_eq!(found.confidence, 90.0);
+        assert_eq!(query.case_sensitive(true).find(&toolbar()), None);
+    }
+
+    #[test]
+    fn regex_query_reports_invalid_patterns() {
+        assert!(matches!(
+            TextQuery::regex("(unclosed"),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+}
@@ -1,27 +1,366 @@
-use std::process::Command;
+use opencv::{
+    core::{AlgorithmHint, CV_8U, Mat},
+    imgcodecs::{IMREAD_COLOR, imread},
+    imgproc::{COLOR_BGR2RGB, COLOR_BGRA2RGB, cvt_color},
+    prelude::*,
+};
+use std::borrow::Cow;
+use std::path::PathBuf;
+use tesseract::Tesseract;
 
+use super::ocr_result::OcrResult;
+use super::preprocess::Preprocessor;
+use crate::error::{Error, Result};
 
-pub fn read_text_from_image(image_path: &str) -> String {
-    let output = Command::new("tesseract")
-        .arg(image_path)
-        .arg("stdout")
-        .arg("-l")
-        .arg("rus+eng") // язык
-        .output();
+/// Languages used when none are given.
+pub const DEFAULT_LANGUAGES: &str = "rus+eng";
 
-    match output {
-        Ok(output) if output.status.success() => {
-            String::from_utf8_lossy(&output.stdout).to_string()
+/// How tesseract splits the image into blocks, lines and words.
+///
+/// The default assumes a page of text; for a UI element that holds a single line
+/// or word the specific modes are both faster and much more reliable.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PageSegMode {
+    /// Fully automatic page segmentation, without orientation detection.
+    #[default]
+    Auto,
+    /// A single column of text of variable sizes.
+    SingleColumn,
+    /// A single uniform block of text.
+    SingleBlock,
+    /// A single text line.
+    SingleLine,
+    /// A single word.
+    SingleWord,
+    /// A single character.
+    SingleChar,
+    /// As much text as possible in no particular order.
+    SparseText,
+    /// A single text line, bypassing tesseract-specific hacks.
+    RawLine,
+}
+
+impl PageSegMode {
+    fn to_tesseract(self) -> tesseract::PageSegMode {
+        use tesseract::PageSegMode as Psm;
+        match self {
+            PageSegMode::Auto => Psm::PsmAuto,
+            PageSegMode::SingleColumn => Psm::PsmSingleColumn,
+            PageSegMode::SingleBlock => Psm::PsmSingleBlock,
+            PageSegMode::SingleLine => Psm::PsmSingleLine,
+            PageSegMode::SingleWord => Psm::PsmSingleWord,
+            PageSegMode::SingleChar => Psm::PsmSingleChar,
+            PageSegMode::SparseText => Psm::PsmSparseText,
+            PageSegMode::RawLine => Psm::PsmRawLine,
+        }
+    }
+}
+
+/// Recognition engine of tesseract.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EngineMode {
+    /// Whatever the traineddata supports, LSTM if available.
+    #[default]
+    Default,
+    /// The legacy engine; needs traineddata with the legacy models.
+    Legacy,
+    /// The LSTM neural network engine.
+    Lstm,
+    /// Both engines combined.
+    Combined,
+}
+
+impl EngineMode {
+    fn to_tesseract(self) -> tesseract::OcrEngineMode {
+        use tesseract::OcrEngineMode as Oem;
+        match self {
+            EngineMode::Default => Oem::Default,
+            EngineMode::Legacy => Oem::TesseractOnly,
+            EngineMode::Lstm => Oem::LstmOnly,
+            EngineMode::Combined => Oem::TesseractLstmCombined,
+        }
+    }
+}
+
+/// Settings of an [`OcrEngine`].
+///
+/// Reading a price or a counter:
+///
+/// ```ignore
+/// let options = OcrOptions::default()
+///     .languages("eng")
+///     .page_seg_mode(PageSegMode::SingleLine)
+///     .whitelist("0123456789.,");
+/// let mut engine = OcrEngine::with_options(&options)?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrOptions {
+    /// Tesseract language codes joined with `+`, e.g. `"eng"` or `"rus+eng"`.
+    pub languages: String,
+    /// Directory with the `.traineddata` files, `None` for the tesseract default
+    /// (`TESSDATA_PREFIX` or the install location).
+    pub tessdata: Option<PathBuf>,
+    pub page_seg_mode: PageSegMode,
+    pub engine_mode: EngineMode,
+    /// Resolution of the images; screen captures carry none and tesseract guesses
+    /// otherwise, which hurts small text. `None` to let it guess.
+    pub dpi: Option<u32>,
+    /// Only these characters are recognized.
+    pub whitelist: Option<String>,
+    /// These characters are never recognized.
+    pub blacklist: Option<String>,
+    /// Image operations applied before recognition, none by default.
+    pub preprocess: Preprocessor,
+}
+
+impl Default for OcrOptions {
+    fn default() -> Self {
+        Self {
+            languages: DEFAULT_LANGUAGES.to_string(),
+            tessdata: None,
+            page_seg_mode: PageSegMode::Auto,
+            engine_mode: EngineMode::Default,
+            dpi: None,
+            whitelist: None,
+            blacklist: None,
+            preprocess: Preprocessor::new(),
+        }
+    }
+}
+
+impl OcrOptions {
+    pub fn languages(mut self, languages: &str) -> Self {
+        self.languages = languages.to_string();
+        self
+    }
+
+    pub fn tessdata(mut self, path: impl Into<PathBuf>) -> Self {
+        self.tessdata = Some(path.into());
+        self
+    }
+
+    pub fn page_seg_mode(mut self, mode: PageSegMode) -> Self {
+        self.page_seg_mode = mode;
+        self
+    }
+
+    pub fn engine_mode(mut self, mode: EngineMode) -> Self {
+        self.engine_mode = mode;
+        self
+    }
+
+    pub fn dpi(mut self, dpi: u32) -> Self {
+        self.dpi = Some(dpi);
+        self
+    }
+
+    pub fn whitelist(mut self, characters: &str) -> Self {
+        self.whitelist = Some(characters.to_string());
+        self
+    }
+
+    pub fn blacklist(mut self, characters: &str) -> Self {
+        self.blacklist = Some(characters.to_string());
+        self
+    }
+
+    /// See [`Preprocessor::ui_label`] and the other presets.
+    pub fn preprocess(mut self, preprocessor: Preprocessor) -> Self {
+        self.preprocess = preprocessor;
+        self
+    }
+}
+
+/// Tesseract engine running in-process through libtesseract.
+///
+/// Initialization loads the language models and takes much longer than the
+/// recognition of a small image, so keep an engine and reuse it. An engine is not
+/// shared between threads: create one per thread, no temporary files are involved.
+pub struct OcrEngine {
+    // Tesseract consumes itself on each step; `None` after a failed step,
+    // re-initialized on the next call
+    api: Option<Tesseract>,
+    options: OcrOptions,
+}
+
+impl OcrEngine {
+    /// Initializes an engine with the default [`OcrOptions`].
+    ///
+    /// # Errors
+    /// Returns [`Error::OcrFailed`] if the languages can not be loaded.
+    pub fn new() -> Result<Self> {
+        Self::with_options(&OcrOptions::default())
+    }
+
+    /// Initializes an engine with `options`.
+    ///
+    /// # Errors
+    /// - [`Error::InvalidArgument`] if the tessdata path is not valid UTF-8 or the
+    ///   DPI is zero
+    /// - [`Error::OcrFailed`] if the languages can not be loaded or a setting is rejected
+    pub fn with_options(options: &OcrOptions) -> Result<Self> {
+        if options.dpi == Some(0) {
+            return Err(Error::InvalidArgument(
+                "ocr dpi must be greater than zero".to_string(),
+            ));
+        }
+
+        let mut engine = Self {
+            api: None,
+            options: options.clone(),
+        };
+        engine.api = Some(engine.init()?);
+        Ok(engine)
+    }
+
+    pub fn options(&self) -> &OcrOptions {
+        &self.options
+    }
+
+    fn init(&self) -> Result<Tesseract> {
+        let options = &self.options;
+        let tessdata = options
+            .tessdata
+            .as_ref()
+            .map(|path| {
+                path.to_str().ok_or_else(|| {
+                    Error::InvalidArgument(format!(
+                        "tessdata path is not valid UTF-8: {}",
+                        path.display()
+                    ))
+                })
+            })
+            .transpose()?;
+
+        let mut api = Tesseract::new_with_oem(
+            tessdata,
+            Some(&options.languages),
+            options.engine_mode.to_tesseract(),
+        )
+        .map_err(ocr_error)?;
+        api.set_page_seg_mode(options.page_seg_mode.to_tesseract());
+
+        if let Some(dpi) = options.dpi {
+            api = api
+                .set_variable("user_defined_dpi", &dpi.to_string())
+                .map_err(ocr_error)?;
         }
-        Ok(output) => {
-            format!(
-                "Tesseract error: {}",
-                String::from_utf8_lossy(&output.stderr)
-            )
+        if let Some(whitelist) = &options.whitelist {
+            api = api
+                .set_variable("tessedit_char_whitelist", whitelist)
+                .map_err(ocr_error)?;
         }
-        Err(e) => {
-            format!("Ошибка при запуске Tesseract: {}", e)
+        if let Some(blacklist) = &options.blacklist {
+            api = api
+                .set_variable("tessedit_char_blacklist", blacklist)
+                .map_err(ocr_error)?;
+        }
+        Ok(api)
+    }
+
+    /// Recognizes the text of a BGR, BGRA or grayscale 8-bit image.
+    ///
+    /// # Errors
+    /// - [`Error::EmptyImage`] if `image` is empty
+    /// - [`Error::OcrFailed`] if tesseract fails
+    pub fn recognize(&mut self, image: &Mat) -> Result<String> {
+        let mut api = self.recognized(image)?;
+        let text = api.get_text().map_err(ocr_error);
+        self.api = Some(api);
+        text
+    }
+
+    /// Recognizes `image` with the area and confidence of every block, line and word.
+    ///
+    /// Areas are in `image` coordinates, also when the options preprocess it.
+    ///
+    /// # Errors
+    /// The errors of [`OcrEngine::recognize`].
+    pub fn recognize_layout(&mut self, image: &Mat) -> Result<OcrResult> {
+        let mut api = self.recognized(image)?;
+        let tsv = api.get_tsv_text(0).map_err(ocr_error);
+        self.api = Some(api);
+
+        let result = OcrResult::from_tsv(&tsv?)?;
+        if self.options.preprocess.is_empty() {
+            return Ok(result);
         }
+        Ok(result.map_areas(|area| self.options.preprocess.to_original(area)))
+    }
+
+    /// Runs the recognition of `image` and returns the engine holding the result.
+    fn recognized(&mut self, image: &Mat) -> Result<Tesseract> {
+        if image.empty() {
+            return Err(Error::EmptyImage("ocr image"));
+        }
+
+        let preprocessed;
+        let image = if self.options.preprocess.is_empty() {
+            image
+        } else {
+            preprocessed = self.options.preprocess.apply(image)?;
+            &preprocessed
+        };
+
+        let frame = to_frame(image)?;
+        let api = match self.api.take() {
+            Some(api) => api,
+            None => self.init()?,
+        };
+
+        let bytes_per_pixel = frame.channels();
+        api.set_frame(
+            frame.data_bytes()?,
+            frame.cols(),
+            frame.rows(),
+            bytes_per_pixel,
+            frame.cols() * bytes_per_pixel,
+        )
+        .map_err(ocr_error)?
+        .recognize()
+        .map_err(ocr_error)
     }
 }
 
+/// Converts `image` to the continuous RGB or grayscale layout tesseract reads.
+fn to_frame(image: &Mat) -> Result<Cow<'_, Mat>> {
+    if image.depth() != CV_8U {
+        return Err(Error::InvalidArgument(
+            "ocr image must be 8-bit".to_string(),
+        ));
+    }
+
+    let code = match image.channels() {
+        1 if image.is_continuous() => return Ok(Cow::Borrowed(image)),
+        1 => return Ok(Cow::Owned(image.try_clone()?)),
+        3 => COLOR_BGR2RGB,
+        4 => COLOR_BGRA2RGB,
+        channels => {
+            return Err(Error::InvalidArgument(format!(
+                "ocr image must have 1, 3 or 4 channels, got {}",
+                channels
+            )));
+        }
+    };
+
+    let mut rgb = Mat::default();
+    cvt_color(image, &mut rgb, code, 0, AlgorithmHint::ALGO_HINT_DEFAULT)?;
+    Ok(Cow::Owned(rgb))
+}
+
+fn ocr_error(e: impl std::fmt::Display) -> Error {
+    Error::OcrFailed(format!("tesseract: {}", e))
+}
+
+/// Recognizes the text of an image file with a one-off [`OcrEngine`].
+pub fn read_text_from_image(image_path: &str, options: &OcrOptions) -> Result<String> {
+    let image = imread(image_path, IMREAD_COLOR)?;
+    if image.empty() {
+        return Err(Error::InvalidArgument(format!(
+            "can not decode image {}",
+            image_path
+        )));
+    }
+
+    OcrEngine::with_options(options)?.recognize(&image)
+}
@@ -0,0 +1,83 @@
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use x11::xlib;
+
+/// Serializes the traps: the Xlib error handler is global to the process.
+static TRAP_LOCK: Mutex<()> = Mutex::new(());
+/// Display whose errors are recorded by the installed handler.
+static TRAPPED_DISPLAY: AtomicPtr<xlib::Display> = AtomicPtr::new(ptr::null_mut());
+/// First error raised on the trapped display: (error code, request code).
+static TRAPPED_ERROR: Mutex<Option<(u8, u8)>> = Mutex::new(None);
+
+unsafe extern "C" fn record_error(
+    display: *mut xlib::Display,
+    event: *mut xlib::XErrorEvent,
+) -> c_int {
+    if display == TRAPPED_DISPLAY.load(Ordering::SeqCst) {
+        let event = unsafe { &*event };
+        let mut trapped = TRAPPED_ERROR.lock().unwrap_or_else(|e| e.into_inner());
+        trapped.get_or_insert((event.error_code, event.request_code));
+    }
+    0
+}
+
+/// Runs the Xlib calls of `requests` with a scoped error handler.
+///
+/// The default Xlib handler prints the error and exits the process; here the
+/// requests are synced and the first protocol error they raised is returned as
+/// its description instead.
+///
+/// # Safety
+/// `display` must be an open connection.
+pub(crate) unsafe fn trap_errors<T>(
+    display: *mut xlib::Display,
+    requests: impl FnOnce() -> T,
+) -> std::result::Result<T, String> {
+    let _lock = TRAP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let (result, error) = unsafe {
+        // Ошибки более ранних запросов остаются прежнему обработчику
+        xlib::XSync(display, xlib::False);
+        TRAPPED_DISPLAY.store(display, Ordering::SeqCst);
+        *TRAPPED_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        let previous = xlib::XSetErrorHandler(Some(record_error));
+
+        let result = requests();
+        xlib::XSync(display, xlib::False);
+
+        xlib::XSetErrorHandler(previous);
+        TRAPPED_DISPLAY.store(ptr::null_mut(), Ordering::SeqCst);
+        let error = TRAPPED_ERROR
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        (result, error)
+    };
+
+    match error {
+        None => Ok(result),
+        Some((error_code, request_code)) => Err(format!(
+            "{} (request {})",
+            unsafe { error_text(display, error_code) },
+            request_code
+        )),
+    }
+}
+
+/// Xlib description of the error `code`, e.g. `BadMatch (invalid parameter attributes)`.
+unsafe fn error_text(display: *mut xlib::Display, code: u8) -> String {
+    let mut buffer = [0 as c_char; 256];
+    unsafe {
+        xlib::XGetErrorText(
+            display,
+            code as c_int,
+            buffer.as_mut_ptr(),
+            buffer.len() as c_int,
+        );
+        std::ffi::CStr::from_ptr(buffer.as_ptr())
+    }
+    .to_string_lossy()
+    .into_owned()
+}
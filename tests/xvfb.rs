@@ -0,0 +1,327 @@
+//! End-to-end runs of the actions against a headless X server.
+//!
+//! Every test starts its own `Xvfb`, which must be on the `PATH`:
+//!
+//! ```text
+//! cargo test --no-default-features --features x11 --test xvfb -- --ignored
+//! ```
+
+#![cfg(all(target_os = "linux", feature = "x11"))]
+
+use mavis_lib::Error;
+use mavis_lib::action::{self, ClickOptions, NotFoundPolicy};
+use mavis_lib::capture::{FrameSource, XImageSource};
+use mavis_lib::display;
+use mavis_lib::input::XTestBackend;
+use mavis_lib::utils::DisplayArea;
+use mavis_lib::vision::ocv::MatchOptions;
+use opencv::prelude::*;
+use std::ffi::CString;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use x11::xlib;
+
+const SCREEN_WIDTH: u32 = 640;
+const SCREEN_HEIGHT: u32 = 480;
+
+/// An `Xvfb` server, killed on drop.
+struct Xvfb {
+    process: Child,
+    name: String,
+}
+
+impl Xvfb {
+    fn start(display: u32) -> Self {
+        Self::with_screens(display, &[(SCREEN_WIDTH, SCREEN_HEIGHT)])
+    }
+
+    /// Starts a server with one screen per size, joined left to right by Xinerama
+    /// when there are several.
+    fn with_screens(display: u32, sizes: &[(u32, u32)]) -> Self {
+        let name = format!(":{}", display);
+        let mut command = Command::new("Xvfb");
+        command.arg(&name);
+        for (screen, (width, height)) in sizes.iter().enumerate() {
+            command.args([
+                "-screen".to_string(),
+                screen.to_string(),
+                format!("{}x{}x24", width, height),
+            ]);
+        }
+        if sizes.len() > 1 {
+            command.arg("+xinerama");
+        }
+        let process = command
+            .args(["-br", "-nolisten", "tcp"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Xvfb must be installed");
+        let xvfb = Self { process, name };
+
+        let started = Instant::now();
+        while XImageSource::open_display(&xvfb.name).is_err() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "Xvfb {} did not start",
+                xvfb.name
+            );
+            thread::sleep(Duration::from_millis(50));
+        }
+        xvfb
+    }
+
+    fn screen(&self) -> DisplayArea {
+        DisplayArea::from_rectangle(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// A window painted with a pattern that appears nowhere else on the black root,
+/// reporting the button presses it receives.
+struct PatternWindow {
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    area: DisplayArea,
+}
+
+impl PatternWindow {
+    fn show(xvfb: &Xvfb, area: DisplayArea) -> Self {
+        let name = CString::new(xvfb.name.as_str()).unwrap();
+        let ((x, y), (width, height)) = area.get_rectangle();
+        unsafe {
+            let display = xlib::XOpenDisplay(name.as_ptr());
+            assert!(!display.is_null(), "can not open {}", xvfb.name);
+            let root = xlib::XDefaultRootWindow(display);
+
+            // Без оконного менеджера позиция окна остаётся заданной
+            let mut attributes: xlib::XSetWindowAttributes = std::mem::zeroed();
+            attributes.override_redirect = xlib::True;
+            attributes.background_pixel = 0xffffff;
+            attributes.event_mask = xlib::ExposureMask | xlib::ButtonPressMask;
+            let window = xlib::XCreateWindow(
+                display,
+                root,
+                x,
+                y,
+                width,
+                height,
+                0,
+                xlib::CopyFromParent,
+                xlib::InputOutput as u32,
+                std::ptr::null_mut(),
+                xlib::CWOverrideRedirect | xlib::CWBackPixel | xlib::CWEventMask,
+                &mut attributes,
+            );
+            xlib::XMapWindow(display, window);
+
+            let mut event: xlib::XEvent = std::mem::zeroed();
+            xlib::XWindowEvent(display, window, xlib::ExposureMask, &mut event);
+
+            let gc = xlib::XCreateGC(display, window, 0, std::ptr::null_mut());
+            let (half_width, half_height) = (width / 2, height / 2);
+            for (color, cell_x, cell_y) in [
+                (0xff0000, 0, 0),
+                (0x00ff00, half_width, 0),
+                (0x0000ff, 0, half_height),
+                (0xffff00, half_width, half_height),
+            ] {
+                xlib::XSetForeground(display, gc, color);
+                xlib::XFillRectangle(
+                    display,
+                    window,
+                    gc,
+                    cell_x as i32,
+                    cell_y as i32,
+                    half_width,
+                    half_height,
+                );
+            }
+            xlib::XSetForeground(display, gc, 0x000000);
+            xlib::XFillRectangle(
+                display,
+                window,
+                gc,
+                width as i32 / 4,
+                height as i32 / 4,
+                width / 2,
+                height / 2,
+            );
+            xlib::XFreeGC(display, gc);
+            xlib::XSync(display, xlib::False);
+
+            Self {
+                display,
+                window,
+                area,
+            }
+        }
+    }
+
+    /// Root coordinates of the next button press, `None` if none comes in `timeout`.
+    fn wait_for_press(&self, timeout: Duration) -> Option<(i32, i32)> {
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            unsafe {
+                let mut event: xlib::XEvent = std::mem::zeroed();
+                let received = xlib::XCheckWindowEvent(
+                    self.display,
+                    self.window,
+                    xlib::ButtonPressMask,
+                    &mut event,
+                );
+                if received != 0 {
+                    return Some((event.button.x_root, event.button.y_root));
+                }
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        None
+    }
+}
+
+impl Drop for PatternWindow {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XDestroyWindow(self.display, self.window);
+            xlib::XCloseDisplay(self.display);
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs Xvfb"]
+fn click_on_target_clicks_the_center_of_the_match() {
+    let xvfb = Xvfb::start(97);
+    let window = PatternWindow::show(&xvfb, DisplayArea::from_rectangle(200, 150, 120, 80));
+
+    let mut frames = XImageSource::open_display(&xvfb.name).unwrap();
+    let mut backend = XTestBackend::open_display(&xvfb.name).unwrap();
+    let target = frames.grab(&window.area).unwrap();
+
+    action::click_on_target(
+        &mut backend,
+        &mut frames,
+        0.95,
+        &target,
+        &MatchOptions::single_scale(),
+        &ClickOptions {
+            active_area: xvfb.screen(),
+            desktop: Some(xvfb.screen()),
+            delay: Duration::from_millis(20),
+        },
+        NotFoundPolicy::Error,
+    )
+    .unwrap();
+
+    assert_eq!(
+        window.wait_for_press(Duration::from_secs(2)),
+        Some(window.area.get_average_point())
+    );
+}
+
+#[test]
+#[ignore = "needs Xvfb"]
+fn click_on_target_searches_only_the_active_area() {
+    let xvfb = Xvfb::start(98);
+    let window = PatternWindow::show(&xvfb, DisplayArea::from_rectangle(400, 300, 120, 80));
+
+    let mut frames = XImageSource::open_display(&xvfb.name).unwrap();
+    let mut backend = XTestBackend::open_display(&xvfb.name).unwrap();
+    let target = frames.grab(&window.area).unwrap();
+
+    let result = action::click_on_target(
+        &mut backend,
+        &mut frames,
+        0.95,
+        &target,
+        &MatchOptions::single_scale(),
+        &ClickOptions {
+            active_area: DisplayArea::from_rectangle(0, 0, 320, 240),
+            desktop: Some(xvfb.screen()),
+            delay: Duration::from_millis(20),
+        },
+        NotFoundPolicy::Error,
+    );
+
+    assert!(
+        matches!(result, Err(Error::TemplateNotFound { .. })),
+        "{:?}",
+        result
+    );
+    assert_eq!(window.wait_for_press(Duration::from_millis(200)), None);
+}
+
+#[test]
+#[ignore = "needs Xvfb"]
+fn grab_outside_of_the_root_window_is_an_error() {
+    let xvfb = Xvfb::start(99);
+    let mut frames = XImageSource::open_display(&xvfb.name).unwrap();
+
+    let result = frames.grab(&DisplayArea::from_rectangle(600, 400, 100, 100));
+
+    assert!(
+        matches!(result, Err(Error::CaptureFailed(_))),
+        "{:?}",
+        result
+    );
+    // Соединение пригодно и после ошибки X сервера
+    assert!(frames.grab(&xvfb.screen()).is_ok());
+}
+
+#[test]
+#[ignore = "needs Xvfb"]
+fn click_on_target_clicks_on_the_second_xinerama_screen() {
+    let xvfb = Xvfb::with_screens(96, &[(SCREEN_WIDTH, SCREEN_HEIGHT), (800, 600)]);
+
+    let monitors = display::xinerama_monitors(Some(&xvfb.name)).unwrap();
+    let bounds: Vec<DisplayArea> = monitors.iter().map(|monitor| monitor.bounds).collect();
+    assert_eq!(
+        bounds,
+        vec![
+            DisplayArea::from_rectangle(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT),
+            DisplayArea::from_rectangle(SCREEN_WIDTH as i32, 0, 800, 600),
+        ]
+    );
+    let desktop = display::bounding_box(&monitors).unwrap();
+
+    let window = PatternWindow::show(
+        &xvfb,
+        DisplayArea::from_rectangle(SCREEN_WIDTH as i32 + 200, 150, 120, 80),
+    );
+    let mut frames = XImageSource::open_display(&xvfb.name).unwrap();
+    let mut backend = XTestBackend::open_display(&xvfb.name).unwrap();
+    let target = frames.grab(&window.area).unwrap();
+
+    // Область поиска захватывает оба экрана
+    let spanning = DisplayArea::from_rectangle(320, 0, 800, SCREEN_HEIGHT);
+    let frame = frames.grab(&spanning).unwrap();
+    assert_eq!((frame.cols(), frame.rows()), (800, SCREEN_HEIGHT as i32));
+
+    action::click_on_target(
+        &mut backend,
+        &mut frames,
+        0.95,
+        &target,
+        &MatchOptions::single_scale(),
+        &ClickOptions {
+            active_area: spanning,
+            desktop: Some(desktop),
+            delay: Duration::from_millis(20),
+        },
+        NotFoundPolicy::Error,
+    )
+    .unwrap();
+
+    assert_eq!(
+        window.wait_for_press(Duration::from_secs(2)),
+        Some(window.area.get_average_point())
+    );
+}